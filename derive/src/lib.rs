@@ -0,0 +1,507 @@
+// Copyright 2025 Aquila Labs of Alberta, Canada <matt@cicero.sh>
+// Licensed under either the Apache License, Version 2.0 OR the MIT License, at your option.
+// You may not use this file except in compliance with one of the Licenses.
+// Apache License text: https://www.apache.org/licenses/LICENSE-2.0
+// MIT License text: https://opensource.org/licenses/MIT
+
+//! Derive macro for falcon-cli commands.  Enable via the `derive` feature of `falcon-cli`
+//! instead of depending on this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    Attribute, Data, DeriveInput, Error, Expr, Fields, GenericArgument, LitStr, PathArguments,
+    Type, parse_macro_input,
+};
+
+/// Derives `CliCommand` for a struct, generating parsing, validation and help from its fields.
+///
+/// Each field becomes a positional parameter or flag, with the first paragraph of its doc
+/// comment used as the description within the help screen.  The struct's own doc comment is
+/// used as the command description, keeping its paragraphs.  Implement `CliRun` for the struct, and its `run()` method will be executed
+/// on a new instance populated from the request.
+///
+/// # Field Types
+///
+/// * `bool` - Boolean flag
+/// * `T` - Required parameter, or required value flag (any type implementing `FromStr`)
+/// * `Option<T>` - Optional parameter or value flag
+/// * `Vec<T>` - Remaining parameters (must be the last parameter), or repeatable value flag
+///
+/// # Attributes
+///
+/// On the struct, `#[cli(...)]` accepts:
+///
+/// * `title = "..."` - Title of the help screen (defaults to the struct name)
+/// * `usage = "..."` - Usage line of the help screen (generated from the parameters and flags if omitted)
+/// * `example = "..."` - Example shown in the help screen, may be given multiple times
+///
+/// On fields, `#[cli(...)]` accepts:
+///
+/// * `param` - Positional parameter (default for all non-bool fields)
+/// * `flag` - Flag (default for bool fields)
+/// * `skip` - Not parsed, set via `Default`
+/// * `count` - Number of times a boolean flag was given (e.g., `-vvv`), for integer fields
+/// * `name = "..."` - Parameter name in help screens (defaults to the uppercase field name)
+/// * `short = "-x"` - Short form of the flag
+/// * `long = "--xyz"` - Long form of the flag (defaults to the field name with dashes)
+/// * `default = "..."` - Value used when not provided, as the single value of a `Vec` flag
+/// * `env = "..."` - Environment variable used for a value flag when not provided (not
+///   supported on parameters, or boolean and `count` flags)
+/// * `format = CliFormat::...` - Format validator for the value
+///
+/// # Example
+///
+/// ```
+/// use falcon_cli::*;
+///
+/// /// Create a new domain name
+/// #[derive(Default, CliCommand)]
+/// #[cli(title = "Create Domain", example = "myapp domain create example.com -i 1.2.3.4")]
+/// struct CreateDomain {
+///     /// Domain name to create
+///     domain: String,
+///     /// Time to live in seconds
+///     #[cli(default = "3600", format = CliFormat::IntegerRange(60..86400))]
+///     ttl: u32,
+///     /// IP address of the domain
+///     #[cli(flag, short = "-i")]
+///     ip_address: String,
+///     /// Tags to apply
+///     #[cli(flag, short = "-t")]
+///     tag: Vec<String>,
+///     /// Nameservers to delegate to
+///     #[cli(flag, default = "ns1.example.com")]
+///     nameserver: Vec<String>,
+///     /// Skip confirmation
+///     #[cli(short = "-n")]
+///     no_confirm: bool,
+/// }
+///
+/// impl CliRun for CreateDomain {
+///     fn run(&self, req: &CliRequest) -> anyhow::Result<()> {
+///         cli_sendln!("Creating {} at {}", self.domain, self.ip_address);
+///         Ok(())
+///     }
+/// }
+///
+/// let mut router = CliRouter::new();
+/// router.add::<CreateDomain>("domain create", vec![], vec![]);
+///
+/// let args = ["domain", "create", "example.com", "-i", "1.2.3.4", "-t", "a", "--tag", "b", "-n"];
+/// let (mut req, cmd) = router.lookup_from(args).command().unwrap();
+/// cmd.spec().unwrap().apply(&mut req).unwrap();
+///
+/// let parsed = CreateDomain::from_request(&req).unwrap();
+/// assert_eq!(parsed.domain, "example.com");
+/// assert_eq!(parsed.ttl, 3600);
+/// assert_eq!(parsed.ip_address, "1.2.3.4");
+/// assert_eq!(parsed.tag, vec!["a", "b"]);
+/// assert_eq!(parsed.nameserver, vec!["ns1.example.com"]);
+/// assert!(parsed.no_confirm);
+/// ```
+#[proc_macro_derive(CliCommand, attributes(cli))]
+pub fn derive_cli_command(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// How a field is populated from the request.
+#[derive(PartialEq)]
+enum FieldKind {
+    Param,
+    Flag,
+    Skip,
+}
+
+/// Wrapper type of a field, if any.
+enum FieldWrapper<'a> {
+    Plain,
+    Option(&'a Type),
+    Vec(&'a Type),
+}
+
+/// Options given via `#[cli(...)]` on a field.
+#[derive(Default)]
+struct FieldOptions {
+    kind: Option<FieldKind>,
+    count: bool,
+    name: Option<String>,
+    short: Option<String>,
+    long: Option<String>,
+    default: Option<String>,
+    env: Option<String>,
+    format: Option<Expr>,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            ident,
+            "CliCommand can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new_spanned(
+            ident,
+            "CliCommand can only be derived for structs with named fields",
+        ));
+    };
+
+    // Struct options
+    let mut title = ident.to_string();
+    let mut usage: Option<String> = None;
+    let mut examples: Vec<String> = vec![];
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("cli")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("title") {
+                title = meta.value()?.parse::<LitStr>()?.value();
+            } else if meta.path.is_ident("usage") {
+                usage = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("example") {
+                examples.push(meta.value()?.parse::<LitStr>()?.value());
+            } else {
+                return Err(meta.error("unknown cli attribute, expected title, usage or example"));
+            }
+            Ok(())
+        })?;
+    }
+    let description = doc_paragraphs(&input.attrs).join("\n\n");
+
+    // Go through fields
+    let mut spec_stmts = vec![];
+    let mut field_inits = vec![];
+    let (mut pos, mut has_skipped, mut has_vec_param) = (0_usize, false, false);
+    for field in fields.named.iter() {
+        let field_ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let opts = field_options(&field.attrs)?;
+        let doc = doc_paragraphs(&field.attrs).into_iter().next().unwrap_or_default();
+        let wrapper = field_wrapper(ty);
+        let is_bool = is_bool(ty);
+
+        let kind = match opts.kind {
+            Some(kind) => kind,
+            None if is_bool || opts.count => FieldKind::Flag,
+            None => FieldKind::Param,
+        };
+
+        match kind {
+            FieldKind::Skip => {
+                has_skipped = true;
+            }
+            FieldKind::Param => {
+                if opts.env.is_some() {
+                    return Err(Error::new_spanned(
+                        field,
+                        "env is only supported on value flags",
+                    ));
+                }
+                if has_vec_param {
+                    return Err(Error::new_spanned(
+                        field,
+                        "a Vec parameter must be the last parameter",
+                    ));
+                }
+                let name = opts.name.unwrap_or_else(|| field_ident.to_string().to_uppercase());
+                let format = format_tokens(&opts.format);
+                let modifier = match (&opts.default, &wrapper) {
+                    (Some(default), _) => quote! { .default(#default) },
+                    (None, FieldWrapper::Plain) => quote! {},
+                    (None, _) => quote! { .optional() },
+                };
+                spec_stmts.push(quote! { spec.add_param(#name, #doc, #format)#modifier; });
+
+                let init = match wrapper {
+                    FieldWrapper::Plain => quote! { req.get_param_as::<#ty>(#pos)? },
+                    FieldWrapper::Option(inner) => quote! {
+                        match req.args.get(#pos) {
+                            ::std::option::Option::Some(_) => ::std::option::Option::Some(req.get_param_as::<#inner>(#pos)?),
+                            ::std::option::Option::None => ::std::option::Option::None,
+                        }
+                    },
+                    FieldWrapper::Vec(inner) => {
+                        has_vec_param = true;
+                        quote! {
+                            (#pos..req.args.len())
+                                .map(|pos| req.get_param_as::<#inner>(pos))
+                                .collect::<::std::result::Result<::std::vec::Vec<#inner>, ::falcon_cli::CliError>>()?
+                        }
+                    }
+                };
+                field_inits.push(quote! { #field_ident: #init });
+
+                pos += 1;
+            }
+            FieldKind::Flag => {
+                let short = opts.short.unwrap_or_default();
+                let long = opts
+                    .long
+                    .unwrap_or_else(|| format!("--{}", field_ident.to_string().replace('_', "-")));
+                let flag_name = if long.is_empty() {
+                    short.to_string()
+                } else {
+                    long.to_string()
+                };
+
+                if (is_bool || opts.count) && opts.env.is_some() {
+                    return Err(Error::new_spanned(
+                        field,
+                        "env is only supported on value flags",
+                    ));
+                }
+
+                let init = if is_bool {
+                    spec_stmts.push(quote! { spec.add_flag(#short, #long, #doc); });
+                    quote! { req.has_flag(#flag_name) }
+                } else if opts.count {
+                    spec_stmts.push(quote! { spec.add_flag(#short, #long, #doc); });
+                    quote! { req.get_flag_count(#flag_name) as _ }
+                } else {
+                    let format = format_tokens(&opts.format);
+                    let mut modifier = match (&opts.default, &wrapper) {
+                        (Some(default), FieldWrapper::Vec(_)) => {
+                            quote! { .repeatable().default(#default) }
+                        }
+                        (None, FieldWrapper::Vec(_)) => quote! { .repeatable() },
+                        (Some(default), _) => quote! { .default(#default) },
+                        (None, FieldWrapper::Plain) => quote! { .required() },
+                        (None, FieldWrapper::Option(_)) => quote! {},
+                    };
+                    if let Some(env) = &opts.env {
+                        modifier.extend(quote! { .env(#env) });
+                    }
+                    spec_stmts.push(
+                        quote! { spec.add_value_flag(#short, #long, #doc, #format)#modifier; },
+                    );
+
+                    match wrapper {
+                        FieldWrapper::Plain => quote! { req.get_flag_as::<#ty>(#flag_name)? },
+                        FieldWrapper::Option(inner) => quote! {
+                            if req.has_flag(#flag_name) {
+                                ::std::option::Option::Some(req.get_flag_as::<#inner>(#flag_name)?)
+                            } else {
+                                ::std::option::Option::None
+                            }
+                        },
+                        FieldWrapper::Vec(inner) => {
+                            quote! { req.get_flag_all_as::<#inner>(#flag_name)? }
+                        }
+                    }
+                };
+                field_inits.push(quote! { #field_ident: #init });
+            }
+        }
+    }
+
+    let usage = usage.unwrap_or_default();
+
+    let rest = if has_skipped {
+        quote! { ..::std::default::Default::default() }
+    } else {
+        quote! {}
+    };
+    let help = if examples.is_empty() {
+        quote! { ::falcon_cli::CliHelpScreen::new(#title, #usage, #description) }
+    } else {
+        quote! {
+            let mut help = ::falcon_cli::CliHelpScreen::new(#title, #usage, #description);
+            #( help.add_example(#examples); )*
+            help
+        }
+    };
+    let spec = if spec_stmts.is_empty() {
+        quote! { ::std::option::Option::Some(::falcon_cli::CliCommandSpec::new()) }
+    } else {
+        quote! {
+            let mut spec = ::falcon_cli::CliCommandSpec::new();
+            #( #spec_stmts )*
+            ::std::option::Option::Some(spec)
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::falcon_cli::CliFromRequest for #ident #ty_generics #where_clause {
+            fn from_request(req: &::falcon_cli::CliRequest) -> ::std::result::Result<Self, ::falcon_cli::CliError> {
+                ::std::result::Result::Ok(Self {
+                    #( #field_inits, )*
+                    #rest
+                })
+            }
+        }
+
+        impl #impl_generics ::falcon_cli::CliCommand for #ident #ty_generics #where_clause {
+            fn process(&self, req: &::falcon_cli::CliRequest) -> ::falcon_cli::anyhow::Result<()> {
+                let cmd = <Self as ::falcon_cli::CliFromRequest>::from_request(req)?;
+                ::falcon_cli::CliRun::run(&cmd, req)
+            }
+
+            fn help(&self) -> ::falcon_cli::CliHelpScreen {
+                #help
+            }
+
+            fn spec(&self) -> ::std::option::Option<::falcon_cli::CliCommandSpec> {
+                #spec
+            }
+        }
+    })
+}
+
+/// Parses the `#[cli(...)]` attributes of a field.
+fn field_options(attrs: &[Attribute]) -> syn::Result<FieldOptions> {
+    let mut opts = FieldOptions::default();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("cli")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("param") {
+                opts.kind = Some(FieldKind::Param);
+            } else if meta.path.is_ident("flag") {
+                opts.kind = Some(FieldKind::Flag);
+            } else if meta.path.is_ident("skip") {
+                opts.kind = Some(FieldKind::Skip);
+            } else if meta.path.is_ident("count") {
+                opts.count = true;
+            } else if meta.path.is_ident("name") {
+                opts.name = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("short") {
+                opts.short = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("long") {
+                opts.long = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("default") {
+                opts.default = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("env") {
+                opts.env = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("format") {
+                opts.format = Some(meta.value()?.parse::<Expr>()?);
+            } else {
+                return Err(meta.error("unknown cli attribute"));
+            }
+            Ok(())
+        })?;
+    }
+
+    // Short or long implies a flag
+    if opts.kind.is_none() && (opts.short.is_some() || opts.long.is_some()) {
+        opts.kind = Some(FieldKind::Flag);
+    }
+
+    Ok(opts)
+}
+
+/// Gets the paragraphs of an item's doc comment, with the lines of each joined by a space.
+fn doc_paragraphs(attrs: &[Attribute]) -> Vec<String> {
+    let mut paragraphs = vec![];
+    let mut lines: Vec<String> = vec![];
+    for attr in attrs.iter().filter(|a| a.path().is_ident("doc")) {
+        if let syn::Meta::NameValue(nv) = &attr.meta
+            && let Expr::Lit(expr) = &nv.value
+            && let syn::Lit::Str(lit) = &expr.lit
+        {
+            // Blank doc lines are empty, while block comments may contain several lines
+            let value = lit.value();
+            for line in value.lines().chain(value.is_empty().then_some("")).map(str::trim) {
+                if !line.is_empty() {
+                    lines.push(line.to_string());
+                } else if !lines.is_empty() {
+                    paragraphs.push(lines.join(" "));
+                    lines.clear();
+                }
+            }
+        }
+    }
+    if !lines.is_empty() {
+        paragraphs.push(lines.join(" "));
+    }
+    paragraphs
+}
+
+/// Gets the format validator tokens, defaulting to `CliFormat::Any`.
+fn format_tokens(format: &Option<Expr>) -> TokenStream2 {
+    match format {
+        Some(expr) => quote! { #expr },
+        None => quote! { ::falcon_cli::CliFormat::Any },
+    }
+}
+
+/// Determines whether the type is wrapped in an `Option` or `Vec`.
+fn field_wrapper(ty: &Type) -> FieldWrapper<'_> {
+    let Type::Path(path) = ty else {
+        return FieldWrapper::Plain;
+    };
+    let Some(segment) = path.path.segments.last() else {
+        return FieldWrapper::Plain;
+    };
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return FieldWrapper::Plain;
+    };
+    let Some(GenericArgument::Type(inner)) = args.args.first() else {
+        return FieldWrapper::Plain;
+    };
+
+    if segment.ident == "Option" {
+        FieldWrapper::Option(inner)
+    } else if segment.ident == "Vec" {
+        FieldWrapper::Vec(inner)
+    } else {
+        FieldWrapper::Plain
+    }
+}
+
+/// Checks whether the type is `bool`.
+fn is_bool(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.path.is_ident("bool"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    fn expand_err(input: DeriveInput) -> String {
+        expand(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn env_only_on_value_flags() {
+        let msg = "env is only supported on value flags";
+        assert_eq!(
+            expand_err(parse_quote! { struct Cmd { #[cli(env = "X")] name: String } }),
+            msg
+        );
+        assert_eq!(
+            expand_err(parse_quote! { struct Cmd { #[cli(env = "X")] force: bool } }),
+            msg
+        );
+        assert_eq!(
+            expand_err(parse_quote! { struct Cmd { #[cli(flag, count, env = "X")] verbose: u8 } }),
+            msg
+        );
+        assert!(
+            expand(parse_quote! { struct Cmd { #[cli(flag, env = "X")] region: String } }).is_ok()
+        );
+    }
+
+    #[test]
+    fn doc_paragraphs_keep_breaks() {
+        let input: DeriveInput = parse_quote! {
+            /// Deploys the application
+            /// to the given environment.
+            ///
+            /// Existing releases are kept.
+            struct Cmd;
+        };
+        assert_eq!(
+            doc_paragraphs(&input.attrs),
+            vec![
+                "Deploys the application to the given environment.",
+                "Existing releases are kept."
+            ]
+        );
+    }
+}
@@ -0,0 +1,582 @@
+// Copyright 2025 Aquila Labs of Alberta, Canada <matt@cicero.sh>
+// Licensed under either the Apache License, Version 2.0 OR the MIT License, at your option.
+// You may not use this file except in compliance with one of the Licenses.
+// Apache License text: https://www.apache.org/licenses/LICENSE-2.0
+// MIT License text: https://opensource.org/licenses/MIT
+
+use crate::CliCommand;
+use crate::error::CliError;
+use crate::help::CliHelpScreen;
+use crate::request::{CliFormat, CliRequest};
+use crate::router::CliRouter;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// Shells that completion scripts can be generated for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CliShell {
+    /// Bash
+    Bash,
+    /// Zsh
+    Zsh,
+    /// Fish
+    Fish,
+    /// PowerShell
+    PowerShell,
+}
+
+impl FromStr for CliShell {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "bash" => Ok(CliShell::Bash),
+            "zsh" => Ok(CliShell::Zsh),
+            "fish" => Ok(CliShell::Fish),
+            "powershell" | "pwsh" => Ok(CliShell::PowerShell),
+            _ => Err(CliError::InvalidParam(
+                0,
+                format!(
+                    "Expected one of (bash / zsh / fish / powershell), got '{}'",
+                    s
+                ),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for CliShell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliShell::Bash => write!(f, "bash"),
+            CliShell::Zsh => write!(f, "zsh"),
+            CliShell::Fish => write!(f, "fish"),
+            CliShell::PowerShell => write!(f, "powershell"),
+        }
+    }
+}
+
+/// Built-in command registered by `CliRouter::enable_completions()`.
+///
+/// Only provides the help screen, as the script itself is generated by `cli_run()`
+/// which has access to the router.
+#[derive(Default)]
+pub(crate) struct CliCompletionsCommand;
+
+impl CliCommand for CliCompletionsCommand {
+    fn process(&self, _req: &CliRequest) -> anyhow::Result<()> {
+        Err(CliError::Generic("Completions can only be generated via cli_run()".to_string()).into())
+    }
+
+    fn help(&self) -> CliHelpScreen {
+        let mut help = CliHelpScreen::new(
+            "Shell Completions",
+            "completions <SHELL>",
+            "Outputs a tab completion script for the specified shell.  Load it within your shell's startup file, for example 'source <(myapp completions bash)'.",
+        );
+        help.add_param("SHELL", "One of: bash, zsh, fish, powershell");
+        help
+    }
+}
+
+impl CliRouter {
+    /// Generates a tab completion script for the specified shell.
+    ///
+    /// The script completes multi-word commands, category names after `help`, and the
+    /// flags of each command along with all global flags.  The binary name used within
+    /// the script is set via `bin_name()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `shell` - The shell to generate the script for
+    /// * `writer` - Where to write the script
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliRouter, CliShell, CliCommand, CliRequest, CliHelpScreen};
+    /// # #[derive(Default)]
+    /// # struct CreateDomain;
+    /// # impl CliCommand for CreateDomain {
+    /// #   fn process(&self, req: &CliRequest) -> anyhow::Result<()> { Ok(()) }
+    /// #   fn help(&self) -> CliHelpScreen { CliHelpScreen::new("", "", "") }
+    /// # }
+    /// let mut router = CliRouter::new();
+    /// router.bin_name("myapp");
+    /// router.add::<CreateDomain>("domain create", vec![], vec!["--ip-address"]);
+    ///
+    /// let mut script = Vec::new();
+    /// router.generate_completions(CliShell::Bash, &mut script).unwrap();
+    /// let script = String::from_utf8(script).unwrap();
+    /// assert!(script.contains("complete -F _myapp myapp"));
+    /// assert!(script.contains("\"domain\") words='create'"));
+    /// ```
+    pub fn generate_completions(&self, shell: CliShell, writer: &mut dyn Write) -> io::Result<()> {
+        let bin_name = self.get_bin_name();
+        let func_name = format!(
+            "_{}",
+            bin_name.chars().map(|c| if c.is_alphanumeric() { c } else { '_' }).collect::<String>()
+        );
+        let table = self.completion_table();
+        let value_flags = self.all_value_flags();
+
+        let script = match shell {
+            CliShell::Bash => bash_script(&bin_name, &func_name, &table, &value_flags),
+            CliShell::Zsh => zsh_script(&bin_name, &func_name, &table, &value_flags),
+            CliShell::Fish => fish_script(&bin_name, &func_name, &table, &value_flags),
+            CliShell::PowerShell => powershell_script(&bin_name, &table, &value_flags),
+        };
+        writer.write_all(script.as_bytes())
+    }
+
+    /// Gets tab completion suggestions for the value of a parameter or flag.
+    ///
+    /// This is what the hidden `__complete <WORDS>...` command executes, which the generated
+    /// completion scripts call with the words typed so far, the last being the partial word
+    /// being completed.  The matching command's `CliCommand::complete()` method is used,
+    /// falling back to the `OneOf`, `File` and `Directory` formats declared within its
+    /// `spec()`.  Commands and flags themselves are completed by the script.
+    ///
+    /// # Arguments
+    ///
+    /// * `words` - The words typed after the binary name, including the partial word
+    ///
+    /// # Returns
+    ///
+    /// Returns the list of suggestions, which is empty if no command matched.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliRouter, CliCommand, CliCommandSpec, CliFormat, CliRequest, CliHelpScreen};
+    /// #[derive(Default)]
+    /// struct Deploy;
+    /// impl CliCommand for Deploy {
+    ///     fn process(&self, req: &CliRequest) -> anyhow::Result<()> { Ok(()) }
+    ///     fn help(&self) -> CliHelpScreen { CliHelpScreen::new("", "", "") }
+    ///
+    ///     fn spec(&self) -> Option<CliCommandSpec> {
+    ///         let mut spec = CliCommandSpec::new();
+    ///         let envs = vec!["staging".to_string(), "production".to_string()];
+    ///         spec.add_param("ENV", "Environment", CliFormat::OneOf(envs));
+    ///         Some(spec)
+    ///     }
+    ///
+    ///     fn complete(&self, req: &CliRequest, partial: &str) -> Vec<String> {
+    ///         match req.completing_flag.as_deref() {
+    ///             Some("--project") => vec!["alpha".to_string(), "beta".to_string()],
+    ///             _ => vec![],
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut router = CliRouter::new();
+    /// router.add::<Deploy>("deploy", vec![], vec!["--project"]);
+    /// assert_eq!(router.complete(&["deploy".into(), "st".into()]), vec!["staging"]);
+    /// assert_eq!(router.complete(&["deploy".into(), "--project".into(), "".into()]), vec!["alpha", "beta"]);
+    /// assert!(router.complete(&["unknown".into(), "".into()]).is_empty());
+    /// ```
+    pub fn complete(&mut self, words: &[String]) -> Vec<String> {
+        let Some((partial, words)) = words.split_last() else {
+            return Vec::new();
+        };
+        let Some(mut args) = self.parse_args(words) else {
+            return Vec::new();
+        };
+        if ["help", "-h"].contains(&args[0].as_str()) {
+            return Vec::new();
+        }
+
+        // Get command
+        let Ok(Some((alias, start, length))) = self.match_handler(&args) else {
+            return Vec::new();
+        };
+        args.drain(start..start + length);
+        let Some(handler) = self.handlers.values().find(|h| h.alias == alias).cloned() else {
+            return Vec::new();
+        };
+        let (mut req, completing_flag) = self.gather_flags(args, &handler);
+        req.completing_flag = completing_flag;
+        if !req.trailing_args.is_empty() {
+            return Vec::new();
+        }
+
+        let Some(cmd) = self.commands.get(&alias) else {
+            return Vec::new();
+        };
+        let suggestions = cmd.complete(&req, partial);
+        if !suggestions.is_empty() {
+            return suggestions;
+        }
+
+        // Fall back to format within specification
+        let Some(spec) = cmd.spec() else {
+            return Vec::new();
+        };
+        let format = match &req.completing_flag {
+            Some(flag) => spec.get_flag(flag).map(|f| f.format.clone()),
+            None => spec.params.get(req.args.len()).map(|p| p.format.clone()),
+        };
+
+        match format {
+            Some(CliFormat::OneOf(options)) => {
+                options.into_iter().filter(|opt| opt.starts_with(partial.as_str())).collect()
+            }
+            Some(CliFormat::File) => complete_path(partial, false),
+            Some(CliFormat::Directory) => complete_path(partial, true),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Builds the map of command paths to the words that may follow them.
+    fn completion_table(&self) -> BTreeMap<String, BTreeSet<String>> {
+        let mut table: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        let globals: Vec<String> = self
+            .global_flags
+            .iter()
+            .flat_map(|gf| [gf.short.to_string(), gf.long.to_string()])
+            .filter(|f| !f.is_empty())
+            .collect();
+
+        // Walk the children tree
+        let mut queue: Vec<(String, &CliRouter)> = vec![(String::new(), self)];
+        while let Some((path, node)) = queue.pop() {
+            let words = table.entry(path.to_string()).or_default();
+            words.extend(node.children.keys().cloned());
+            words.extend(globals.iter().cloned());
+            if let Some(alias) = &node.handler_alias {
+                words.extend(self.known_flags(alias));
+            }
+
+            for (segment, child) in node.children.iter() {
+                let child_path = format!("{} {}", path, segment).trim().to_string();
+                queue.push((child_path, child));
+            }
+        }
+        table.entry(String::new()).or_default().insert("help".to_string());
+        if self.completions_enabled {
+            table
+                .entry("completions".to_string())
+                .or_default()
+                .extend(["bash", "zsh", "fish", "powershell"].iter().map(|s| s.to_string()));
+        }
+
+        // Help, along with command and category names
+        let mut names: Vec<&String> = self.commands.keys().collect();
+        names.extend(self.categories.keys());
+        for name in names {
+            let mut path = "help".to_string();
+            for segment in name.split_whitespace() {
+                table.entry(path.to_string()).or_default().insert(segment.to_string());
+                path = format!("{} {}", path, segment);
+            }
+        }
+
+        table
+    }
+
+    /// Gets all flags that expect a value, across all commands and global flags.
+    fn all_value_flags(&self) -> BTreeSet<String> {
+        let mut flags: BTreeSet<String> =
+            self.handlers.values().flat_map(|h| h.value_flags.iter().cloned()).collect();
+        for gf in self.global_flags.iter().filter(|gf| gf.is_value) {
+            flags.extend(
+                [gf.short.to_string(), gf.long.to_string()].into_iter().filter(|f| !f.is_empty()),
+            );
+        }
+        flags
+    }
+}
+
+/// Lists the files and directories matching a partially typed path, with directories
+/// suffixed by a `/`.
+fn complete_path(partial: &str, dirs_only: bool) -> Vec<String> {
+    let (dir, prefix) = match partial.rfind('/') {
+        Some(pos) => (&partial[..pos + 1], &partial[pos + 1..]),
+        None => ("", partial),
+    };
+
+    let Ok(entries) = fs::read_dir(if dir.is_empty() { "." } else { dir }) else {
+        return Vec::new();
+    };
+
+    let mut paths: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+
+            let is_dir = entry.path().is_dir();
+            match (is_dir, dirs_only) {
+                (true, _) => Some(format!("{}{}/", dir, name)),
+                (false, false) => Some(format!("{}{}", dir, name)),
+                (false, true) => None,
+            }
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Joins words into a single space delimited string.
+fn join(words: &BTreeSet<String>) -> String {
+    words.iter().map(|w| w.as_str()).collect::<Vec<&str>>().join(" ")
+}
+
+fn bash_script(
+    bin_name: &str,
+    func_name: &str,
+    table: &BTreeMap<String, BTreeSet<String>>,
+    value_flags: &BTreeSet<String>,
+) -> String {
+    let mut cases = String::new();
+    for (path, words) in table.iter() {
+        cases += &format!(
+            "            \"{}\") words='{}'; break ;;\n",
+            path,
+            join(words)
+        );
+    }
+    let value_case = if value_flags.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "            {}) skip=1 ;;\n",
+            value_flags.iter().map(|f| f.as_str()).collect::<Vec<&str>>().join("|")
+        )
+    };
+
+    format!(
+        r#"# bash completion for {bin_name}
+{func_name}() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}" cmd_path="" words="" skip=0 word i
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        word="${{COMP_WORDS[i]}}"
+        if ((skip)); then
+            skip=0
+            continue
+        fi
+        case "$word" in
+            --) return 0 ;;
+{value_case}            -*) ;;
+            *) cmd_path="${{cmd_path:+$cmd_path }}$word" ;;
+        esac
+    done
+
+    while :; do
+        case "$cmd_path" in
+{cases}        esac
+        if [[ "$cmd_path" == *" "* ]]; then cmd_path="${{cmd_path% *}}"; else cmd_path=""; fi
+    done
+
+    # Values of parameters and flags are provided by the binary itself
+    local values=""
+    if [[ "$cur" != -* ]]; then
+        values="$("${{COMP_WORDS[0]}}" __complete "${{COMP_WORDS[@]:1:COMP_CWORD-1}}" "$cur" 2>/dev/null)"
+    fi
+    if ((skip)); then words=""; fi
+
+    local IFS=$'\n'
+    COMPREPLY=($(compgen -W "${{words// /$'\n'}}"$'\n'"$values" -- "$cur"))
+    if [[ ${{#COMPREPLY[@]}} -eq 1 && "${{COMPREPLY[0]}}" == */ ]]; then compopt -o nospace; fi
+}}
+complete -F {func_name} {bin_name}
+"#
+    )
+}
+
+fn zsh_script(
+    bin_name: &str,
+    func_name: &str,
+    table: &BTreeMap<String, BTreeSet<String>>,
+    value_flags: &BTreeSet<String>,
+) -> String {
+    let mut cases = String::new();
+    for (path, words) in table.iter() {
+        cases += &format!(
+            "            \"{}\") candidates=({}); break ;;\n",
+            path,
+            join(words)
+        );
+    }
+    let value_case = if value_flags.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "            {}) skip=1 ;;\n",
+            value_flags.iter().map(|f| f.as_str()).collect::<Vec<&str>>().join("|")
+        )
+    };
+
+    format!(
+        r#"#compdef {bin_name}
+{func_name}() {{
+    local cmd_path="" word i skip=0
+    local -a candidates
+    for ((i = 2; i < CURRENT; i++)); do
+        word="${{words[i]}}"
+        if ((skip)); then
+            skip=0
+            continue
+        fi
+        case "$word" in
+            --) return 0 ;;
+{value_case}            -*) ;;
+            *) cmd_path="${{cmd_path:+$cmd_path }}$word" ;;
+        esac
+    done
+
+    while :; do
+        case "$cmd_path" in
+{cases}        esac
+        if [[ "$cmd_path" == *" "* ]]; then cmd_path="${{cmd_path% *}}"; else cmd_path=""; fi
+    done
+
+    # Values of parameters and flags are provided by the binary itself
+    local -a values
+    if [[ "${{words[CURRENT]}}" != -* ]]; then
+        values=(${{(f)"$("${{words[1]}}" __complete "${{(@)words[2,CURRENT-1]}}" "${{words[CURRENT]}}" 2>/dev/null)"}})
+    fi
+    if ((skip)); then candidates=(); fi
+
+    compadd -- "${{candidates[@]}}"
+    compadd -S '' -- ${{(M)values[@]:#*/}}
+    compadd -- ${{values[@]:#*/}}
+}}
+
+if [ "$funcstack[1]" = "{func_name}" ]; then
+    {func_name} "$@"
+else
+    compdef {func_name} {bin_name}
+fi
+"#
+    )
+}
+
+fn fish_script(
+    bin_name: &str,
+    func_name: &str,
+    table: &BTreeMap<String, BTreeSet<String>>,
+    value_flags: &BTreeSet<String>,
+) -> String {
+    let mut cases = String::new();
+    for (path, words) in table.iter() {
+        cases += &format!(
+            "            case '{}'\n                printf '%s\\n' {}\n                return\n",
+            path,
+            join(words)
+        );
+    }
+    let value_case = if value_flags.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "            case {}\n                set skip 1\n",
+            value_flags.iter().map(|f| format!("'{}'", f)).collect::<Vec<String>>().join(" ")
+        )
+    };
+
+    format!(
+        r#"# fish completion for {bin_name}
+function {func_name}
+    set -l tokens (commandline -opc)
+    set -e tokens[1]
+    set -l cmd_path ''
+    set -l skip 0
+    for word in $tokens
+        if test $skip -eq 1
+            set skip 0
+            continue
+        end
+        switch $word
+            case '--'
+                return
+{value_case}            case '-*'
+            case '*'
+                set cmd_path (string trim -- "$cmd_path $word")
+        end
+    end
+
+    # Values of parameters and flags are provided by the binary itself
+    set -l cur (commandline -ct)
+    if not string match -q -- '-*' $cur
+        set -l bin (commandline -opc)[1]
+        $bin __complete $tokens $cur 2>/dev/null
+    end
+    if test $skip -eq 1
+        return
+    end
+
+    while true
+        switch "$cmd_path"
+{cases}        end
+        if string match -q '* *' -- $cmd_path
+            set cmd_path (string replace -r ' [^ ]*$' '' -- $cmd_path)
+        else
+            set cmd_path ''
+        end
+    end
+end
+complete -c {bin_name} -f -a '({func_name})'
+"#
+    )
+}
+
+fn powershell_script(
+    bin_name: &str,
+    table: &BTreeMap<String, BTreeSet<String>>,
+    value_flags: &BTreeSet<String>,
+) -> String {
+    let mut entries = String::new();
+    for (path, words) in table.iter() {
+        let words = words.iter().map(|w| format!("'{}'", w)).collect::<Vec<String>>().join(", ");
+        entries += &format!("        '{}' = @({})\n", path, words);
+    }
+    let value_flags =
+        value_flags.iter().map(|f| format!("'{}'", f)).collect::<Vec<String>>().join(", ");
+
+    format!(
+        r#"# PowerShell completion for {bin_name}
+Register-ArgumentCompleter -Native -CommandName '{bin_name}' -ScriptBlock {{
+    param($wordToComplete, $commandAst, $cursorPosition)
+    $tokens = @($commandAst.CommandElements | Select-Object -Skip 1 | ForEach-Object {{ $_.ToString() }})
+    if ($wordToComplete -ne '') {{
+        $tokens = @($tokens | Select-Object -First ($tokens.Count - 1))
+    }}
+
+    $valueFlags = @({value_flags})
+    $cmdPath = @()
+    $skip = $false
+    foreach ($word in $tokens) {{
+        if ($skip) {{ $skip = $false; continue }}
+        if ($word -eq '--') {{ return }}
+        if ($valueFlags -contains $word) {{ $skip = $true }}
+        elseif ($word -notlike '-*') {{ $cmdPath += $word }}
+    }}
+
+    $table = @{{
+{entries}    }}
+    $key = $cmdPath -join ' '
+    while (-not $table.ContainsKey($key)) {{
+        $key = $key -replace ' ?[^ ]*$', ''
+    }}
+
+    # Values of parameters and flags are provided by the binary itself
+    $values = @()
+    if ($wordToComplete -notlike '-*') {{
+        $values = @(& $commandAst.CommandElements[0].ToString() __complete @tokens $wordToComplete 2>$null)
+    }}
+    $words = if ($skip) {{ @() }} else {{ @($table[$key]) }}
+
+    $words + $values | Where-Object {{ $_ -like "$wordToComplete*" }} | ForEach-Object {{
+        [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+    }}
+}}
+"#
+    )
+}
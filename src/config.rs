@@ -0,0 +1,333 @@
+// Copyright 2025 Aquila Labs of Alberta, Canada <matt@cicero.sh>
+// Licensed under either the Apache License, Version 2.0 OR the MIT License, at your option.
+// You may not use this file except in compliance with one of the Licenses.
+// Apache License text: https://www.apache.org/licenses/LICENSE-2.0
+// MIT License text: https://opensource.org/licenses/MIT
+
+use crate::error::CliError;
+use crate::spec::CliCommandSpec;
+use indexmap::IndexMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Extensions of the supported configuration file formats, in order of preference.
+const EXTENSIONS: [&str; 3] = ["toml", "json", "ini"];
+
+/// Values loaded from TOML, JSON or INI configuration files.
+///
+/// Keys are flattened into a single level, with nested tables and INI sections joined by a
+/// period, and are case insensitive with underscores and dashes treated the same.  For
+/// example, `region` within the `[deploy]` table is stored as `deploy.region`.  Arrays and
+/// null values are skipped.
+///
+/// When enabled via `CliRouter::enable_config()`, the loaded values are used for global
+/// flags and command value flags, whether declared via `CliCommand::spec()` or given to
+/// `CliRouter::add()`, that were not provided on the command line or via an environment
+/// variable.  Requires the `config` feature.
+///
+/// # Example
+///
+/// ```
+/// use falcon_cli::CliConfig;
+///
+/// let mut config = CliConfig::new();
+/// config.parse("region = \"us-east\"\n\n[deploy]\nlog_level = \"debug\"\n", "toml", "app.toml").unwrap();
+/// config.parse("{\"deploy\": {\"timeout\": 30}}", "json", "app.json").unwrap();
+///
+/// assert_eq!(config.get("region"), Some("us-east"));
+/// assert_eq!(config.get("deploy.log-level"), Some("debug"));
+/// assert_eq!(config.get("deploy.timeout"), Some("30"));
+/// assert_eq!(config.source("deploy.timeout").unwrap().to_str(), Some("app.json"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CliConfig {
+    /// Files that were loaded, from lowest to highest precedence.
+    pub files: Vec<PathBuf>,
+    /// Values keyed by their flattened name, along with the file each was loaded from.
+    pub values: IndexMap<String, (String, PathBuf)>,
+}
+
+impl CliConfig {
+    /// Creates a new, empty configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the configuration files from the standard locations.
+    ///
+    /// Files are loaded in the below order, with values from later files taking precedence.
+    /// For each location, the first file that exists with a `.toml`, `.json` or `.ini`
+    /// extension is loaded.
+    ///
+    /// * `config.*` within the user's configuration directory, such as `~/.config/<name>/`,
+    ///   or `$XDG_CONFIG_HOME/<name>/` when set.
+    /// * `.<name>.*` within the current directory, for project specific settings.
+    /// * The file at `path`, if given.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the application, used to locate its files
+    /// * `path` - Additional file to load, such as given via a `--config` flag
+    ///
+    /// # Returns
+    ///
+    /// Returns the merged configuration, or an error if a file could not be parsed or the
+    /// file at `path` does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use falcon_cli::CliConfig;
+    ///
+    /// let config = CliConfig::load("myapp", Some("deploy.toml")).unwrap();
+    /// if let Some(region) = config.get("region") {
+    ///     println!("Region: {}", region);
+    /// }
+    /// ```
+    pub fn load(name: &str, path: Option<&str>) -> Result<Self, CliError> {
+        let mut config = Self::new();
+
+        let user_dir = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+            .or_else(|| env::var_os("APPDATA").map(PathBuf::from));
+        if let Some(dir) = user_dir {
+            config.load_first(&dir.join(name).join("config"))?;
+        }
+        if let Ok(dir) = env::current_dir() {
+            config.load_first(&dir.join(format!(".{}", name)))?;
+        }
+        if let Some(path) = path {
+            config.load_file(path)?;
+        }
+
+        Ok(config)
+    }
+
+    /// Loads a single configuration file, with its values taking precedence over those
+    /// already loaded.
+    ///
+    /// The format is determined by the file's extension, with `.conf` and `.cfg` files
+    /// parsed as INI.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Location of the file
+    ///
+    /// # Returns
+    ///
+    /// Returns an error if the file could not be read or parsed.
+    pub fn load_file(&mut self, path: &str) -> Result<(), CliError> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            CliError::Generic(format!("Unable to read config file {}: {}", path, e))
+        })?;
+        let ext = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or_default();
+        self.parse(&contents, ext, path)
+    }
+
+    /// Parses configuration contents, with its values taking precedence over those already
+    /// loaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `contents` - The contents to parse
+    /// * `format` - One of `toml`, `json` or `ini` (also `conf` or `cfg`)
+    /// * `file` - The file the contents were read from, reported as the source of its values
+    ///
+    /// # Returns
+    ///
+    /// Returns an error if the format is not supported or the contents could not be parsed.
+    pub fn parse(&mut self, contents: &str, format: &str, file: &str) -> Result<(), CliError> {
+        let mut values = Vec::new();
+        match format.to_lowercase().as_str() {
+            "toml" => {
+                let table = contents.parse::<toml::Table>().map_err(|e| parse_error(file, e))?;
+                flatten_toml("", &table, &mut values);
+            }
+            "json" => {
+                let value = serde_json::from_str::<serde_json::Value>(contents)
+                    .map_err(|e| parse_error(file, e))?;
+                let serde_json::Value::Object(map) = value else {
+                    return Err(parse_error(file, "expected an object"));
+                };
+                flatten_json("", &map, &mut values);
+            }
+            "ini" | "conf" | "cfg" => {
+                values = parse_ini(contents).map_err(|e| parse_error(file, e))?
+            }
+            _ => {
+                return Err(CliError::Generic(format!(
+                    "Unsupported config file format, {} (expected toml, json or ini)",
+                    file
+                )));
+            }
+        }
+
+        let file = PathBuf::from(file);
+        for (key, value) in values {
+            self.values.insert(key, (value, file.clone()));
+        }
+        self.files.push(file);
+        Ok(())
+    }
+
+    /// Gets a value by its flattened name, such as `deploy.region`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(&normalize_key(key)).map(|(value, _)| value.as_str())
+    }
+
+    /// Gets the file a value was loaded from.
+    pub fn source(&self, key: &str) -> Option<&Path> {
+        self.values.get(&normalize_key(key)).map(|(_, file)| file.as_path())
+    }
+
+    /// Gets the value for a flag's long form, preferring the value within the command's table
+    /// (eg. `deploy.region`) over the top level value (eg. `region`).
+    pub(crate) fn lookup(&self, cmd_alias: &str, long: &str) -> Option<&(String, PathBuf)> {
+        let name = long.trim_start_matches('-');
+        if name.is_empty() {
+            return None;
+        } else if !cmd_alias.is_empty()
+            && let Some(entry) = self.values.get(&normalize_key(&format!("{}.{}", cmd_alias, name)))
+        {
+            return Some(entry);
+        }
+        self.values.get(&normalize_key(name))
+    }
+
+    /// Sets the configured values of the value flags within a command's specification.
+    pub(crate) fn extend_spec(&self, spec: &mut CliCommandSpec, cmd_alias: &str) {
+        for flag in spec.flags.iter_mut().filter(|f| f.is_value) {
+            if let Some(entry) = self.lookup(cmd_alias, &flag.long) {
+                flag.config = Some(entry.clone());
+            }
+        }
+    }
+
+    /// Loads the first file that exists with one of the supported extensions.
+    fn load_first(&mut self, base: &Path) -> Result<(), CliError> {
+        match candidates(base).into_iter().find(|p| p.is_file()) {
+            Some(path) => self.load_file(&path.to_string_lossy()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Gets the paths of a file with each of the supported extensions appended, keeping any
+/// dots already within its name (eg. `.my.app.toml`).
+fn candidates(base: &Path) -> Vec<PathBuf> {
+    let name = base.file_name().unwrap_or_default().to_string_lossy();
+    EXTENSIONS.iter().map(|ext| base.with_file_name(format!("{}.{}", name, ext))).collect()
+}
+
+/// Normalizes a key so lookups are case insensitive, treat underscores and dashes the same,
+/// and accept spaces between command segments (eg. `domain create.ttl`).
+fn normalize_key(key: &str) -> String {
+    key.trim().to_lowercase().replace('_', "-").replace(' ', ".")
+}
+
+fn parse_error(file: &str, err: impl std::fmt::Display) -> CliError {
+    CliError::Generic(format!("Unable to parse config file {}: {}", file, err))
+}
+
+fn flatten_toml(prefix: &str, table: &toml::Table, values: &mut Vec<(String, String)>) {
+    for (key, value) in table.iter() {
+        let key = normalize_key(&format!("{}{}", prefix, key));
+        let value = match value {
+            toml::Value::String(s) => s.to_string(),
+            toml::Value::Table(child) => {
+                flatten_toml(&format!("{}.", key), child, values);
+                continue;
+            }
+            toml::Value::Array(_) => continue,
+            other => other.to_string(),
+        };
+        values.push((key, value));
+    }
+}
+
+fn flatten_json(
+    prefix: &str,
+    map: &serde_json::Map<String, serde_json::Value>,
+    values: &mut Vec<(String, String)>,
+) {
+    for (key, value) in map.iter() {
+        let key = normalize_key(&format!("{}{}", prefix, key));
+        let value = match value {
+            serde_json::Value::String(s) => s.to_string(),
+            serde_json::Value::Object(child) => {
+                flatten_json(&format!("{}.", key), child, values);
+                continue;
+            }
+            serde_json::Value::Array(_) | serde_json::Value::Null => continue,
+            other => other.to_string(),
+        };
+        values.push((key, value));
+    }
+}
+
+/// Parses INI contents, with `;` and `#` comment lines, `[section]` headers, and `key = value`
+/// or `key: value` entries whose values may be quoted.
+fn parse_ini(contents: &str) -> Result<Vec<(String, String)>, String> {
+    let mut values = Vec::new();
+    let mut section = String::new();
+
+    for (num, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        } else if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = normalize_key(name);
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once(['=', ':']) else {
+            return Err(format!("expected key = value on line {}", num + 1));
+        };
+        let key = if section.is_empty() {
+            normalize_key(key)
+        } else {
+            format!("{}.{}", section, normalize_key(key))
+        };
+        let value = value.trim();
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|q| value.strip_prefix(*q).and_then(|v| v.strip_suffix(*q)))
+            .unwrap_or(value);
+        values.push((key, value.to_string()));
+    }
+
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn candidates_keep_dotted_names() {
+        let paths = candidates(Path::new("/home/user/.my.app"));
+        assert_eq!(paths[0], PathBuf::from("/home/user/.my.app.toml"));
+        assert_eq!(paths[2], PathBuf::from("/home/user/.my.app.ini"));
+
+        let paths = candidates(&Path::new("/home/user/.config/my.app").join("config"));
+        assert_eq!(
+            paths[1],
+            PathBuf::from("/home/user/.config/my.app/config.json")
+        );
+    }
+
+    #[test]
+    fn load_dotted_name() {
+        let dir = env::temp_dir().join("falcon-config-test");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(".my.app.toml"), "region = \"eu-west\"\n").unwrap();
+
+        let mut config = CliConfig::new();
+        config.load_first(&dir.join(".my.app")).unwrap();
+        assert_eq!(config.get("region"), Some("eu-west"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -9,8 +9,10 @@ use std::fmt;
 /// Error types that can occur during CLI command processing.
 ///
 /// This enum represents all possible errors that can be returned by CLI commands,
-/// including missing parameters, invalid flags, and generic errors.
+/// including missing parameters, invalid flags, and generic errors.  New variants may be
+/// added in minor releases, so matches should include a wildcard arm.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum CliError {
     /// Required parameters were not provided or are invalid.
     MissingParams,
@@ -19,6 +21,12 @@ pub enum CliError {
     /// A parameter at a specific position failed validation.
     /// Contains the position (0-indexed) and an error message describing the issue.
     InvalidParam(usize, String),
+    /// A flag's value failed validation.
+    /// Contains the flag name and an error message describing the issue.
+    InvalidFlag(String, String),
+    /// A flag the command does not declare was provided.
+    /// Contains the flag, and the most similar declared flag if any.
+    UnknownFlag(String, Option<String>),
     /// A generic error with a custom message.
     Generic(String),
 }
@@ -33,11 +41,89 @@ impl fmt::Display for CliError {
             CliError::InvalidParam(pos, msg) => {
                 write!(f, "Invalid parameter at position {}: {}", pos, msg)
             }
+            CliError::InvalidFlag(flag, msg) => {
+                write!(f, "Invalid value for flag {}: {}", flag, msg)
+            }
+            CliError::UnknownFlag(flag, Some(similar)) => {
+                write!(f, "Unknown flag {}, did you mean {}?", flag, similar)
+            }
+            CliError::UnknownFlag(flag, None) => write!(f, "Unknown flag {}", flag),
             CliError::Generic(msg) => write!(f, "{}", msg),
         }
     }
 }
 
+/// Process exit codes returned by `cli_run()` when a command fails.
+///
+/// Each `CliError` variant maps to its own code, with any other error returned by a
+/// command using the `other` code.  The defaults follow the BSD `sysexits.h` conventions,
+/// and may be changed via `CliRouter::exit_codes()`.
+#[derive(Debug, Clone, Copy)]
+pub struct CliExitCodes {
+    /// Exit code for `CliError::MissingParams` (default 64, EX_USAGE).
+    pub missing_params: u8,
+    /// Exit code for `CliError::MissingFlag` (default 64, EX_USAGE).
+    pub missing_flag: u8,
+    /// Exit code for `CliError::InvalidParam` (default 65, EX_DATAERR).
+    pub invalid_param: u8,
+    /// Exit code for `CliError::InvalidFlag` (default 65, EX_DATAERR).
+    pub invalid_flag: u8,
+    /// Exit code for `CliError::UnknownFlag` (default 64, EX_USAGE).
+    pub unknown_flag: u8,
+    /// Exit code for `CliError::Generic` (default 1).
+    pub generic: u8,
+    /// Exit code for any other error returned by a command (default 1).
+    pub other: u8,
+    /// Exit code when arguments were given but no command matched, including when similar
+    /// commands were suggested or a suggestion was declined, or when a command prefix is
+    /// ambiguous (default 64, EX_USAGE).
+    pub not_found: u8,
+}
+
+impl Default for CliExitCodes {
+    fn default() -> Self {
+        Self {
+            missing_params: 64,
+            missing_flag: 64,
+            invalid_param: 65,
+            invalid_flag: 65,
+            unknown_flag: 64,
+            generic: 1,
+            other: 1,
+            not_found: 64,
+        }
+    }
+}
+
+impl CliExitCodes {
+    /// Gets the exit code for an error returned by a command.
+    ///
+    /// # Arguments
+    ///
+    /// * `err` - The error returned by the command's `process()` method
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use falcon_cli::{CliError, CliExitCodes};
+    ///
+    /// let codes = CliExitCodes::default();
+    /// assert_eq!(codes.get(&CliError::MissingParams.into()), 64);
+    /// assert_eq!(codes.get(&anyhow::anyhow!("Connection refused")), 1);
+    /// ```
+    pub fn get(&self, err: &anyhow::Error) -> u8 {
+        match err.downcast_ref::<CliError>() {
+            Some(CliError::MissingParams) => self.missing_params,
+            Some(CliError::MissingFlag(_)) => self.missing_flag,
+            Some(CliError::InvalidParam(_, _)) => self.invalid_param,
+            Some(CliError::InvalidFlag(_, _)) => self.invalid_flag,
+            Some(CliError::UnknownFlag(_, _)) => self.unknown_flag,
+            Some(CliError::Generic(_)) => self.generic,
+            None => self.other,
+        }
+    }
+}
+
 impl From<std::io::Error> for CliError {
     fn from(err: std::io::Error) -> Self {
         CliError::Generic(err.to_string())
@@ -4,7 +4,10 @@
 // Apache License text: https://www.apache.org/licenses/LICENSE-2.0
 // MIT License text: https://opensource.org/licenses/MIT
 use crate::CliCommand;
-use crate::router::CliRouter;
+use crate::macros::format_wrapped;
+use crate::output::wrap_width;
+use crate::router::{CliRouter, env_name};
+use crate::style::paint;
 use crate::*;
 use indexmap::{IndexMap, indexmap};
 
@@ -114,62 +117,151 @@ impl CliHelpScreen {
     /// Renders and displays the help screen for a specific CLI command.
     ///
     /// This method is automatically executed when the first argument passed via the command line
-    /// is 'help' or '-h'. It should not typically be called manually.
+    /// is 'help' or '-h'. It should not typically be called manually.  As the router is not
+    /// available, environment variables derived from `CliRouter::env_prefix()` are not
+    /// displayed, unlike with `format()`.
     ///
     /// # Arguments
     ///
     /// * `cmd` - The CLI command to display help for
     /// * `cmd_alias` - The primary alias/name of the command
     /// * `shortcuts` - List of shortcut aliases for the command
+    #[allow(clippy::borrowed_box, clippy::ptr_arg)]
     pub fn render(cmd: &Box<dyn CliCommand>, cmd_alias: &String, shortcuts: &Vec<String>) {
-        // Get help screen
+        cli_write(
+            CliStream::Stdout,
+            &Self::format_screen(cmd.help(), cmd.spec(), cmd_alias, shortcuts),
+        );
+    }
+
+    /// Renders the help screen for a registered command into a string.
+    ///
+    /// The parameters and flags declared via `CliCommand::spec()` are added to the help
+    /// screen, and the environment variables of value flags are displayed, including those
+    /// derived from `CliRouter::env_prefix()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `router` - The CLI router containing all registered commands
+    /// * `cmd_alias` - The primary alias/name of the command
+    ///
+    /// # Returns
+    ///
+    /// Returns the help screen exactly as displayed for `help <command>`, or an empty string
+    /// if the command does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliCommand, CliRequest, CliHelpScreen, CliRouter};
+    /// # #[derive(Default)]
+    /// # struct Deploy;
+    /// # impl CliCommand for Deploy {
+    /// #   fn process(&self, req: &CliRequest) -> anyhow::Result<()> { Ok(()) }
+    /// fn help(&self) -> CliHelpScreen {
+    ///     let mut help = CliHelpScreen::new("Deploy", "deploy <ENV>", "Deploys the application");
+    ///     help.add_param("ENV", "Environment to deploy to");
+    ///     help.add_flag("-r|--region", "Region to deploy to");
+    ///     help
+    /// }
+    /// # }
+    ///
+    /// let mut router = CliRouter::new();
+    /// router.env_prefix("MYAPP");
+    /// router.add::<Deploy>("deploy", vec!["d"], vec!["--region"]);
+    ///
+    /// let output = CliHelpScreen::format(&router, "deploy");
+    /// assert!(output.contains("-- Deploy\n"));
+    /// assert!(output.contains("    d <ENV>\n"));
+    /// assert!(output.contains("    ENV    Environment to deploy to\n"));
+    /// assert!(output.contains("Region to deploy to [env: MYAPP_REGION]\n"));
+    /// assert!(CliHelpScreen::format(&router, "unknown").is_empty());
+    /// ```
+    pub fn format(router: &CliRouter, cmd_alias: &str) -> String {
+        let alias = cmd_alias.to_lowercase();
+        let Some(cmd) = router.commands.get(&alias) else {
+            return String::new();
+        };
+        let shortcuts = router
+            .handlers
+            .values()
+            .find(|h| h.alias == alias)
+            .map(|h| h.shortcuts.clone())
+            .unwrap_or_default();
+
+        // Value flags given to `add()` are only noted when the help screen lists them
         let help = cmd.help();
+        let declared = cmd.spec();
+        let mut spec = router.command_spec(cmd.as_ref(), &alias);
+        spec.flags.retain(|flag| {
+            declared.as_ref().is_some_and(|d| d.flags.iter().any(|f| f.names() == flag.names()))
+                || flag.help_key(&help).is_some()
+        });
+
+        let spec = (declared.is_some() || !spec.flags.is_empty()).then_some(spec);
+        Self::format_screen(help, spec, &alias, &shortcuts)
+    }
 
-        // Display basics
-        cli_header(help.title.as_str());
-        cli_sendln!("USAGE\n");
-        cli_sendln!(format!("    {}\n", help.usage).as_str());
+    /// Renders a help screen, extended with the given specification.
+    fn format_screen(
+        mut help: CliHelpScreen,
+        spec: Option<CliCommandSpec>,
+        cmd_alias: &str,
+        shortcuts: &[String],
+    ) -> String {
+        if let Some(spec) = spec {
+            if help.usage.is_empty() {
+                help.usage = spec.usage(cmd_alias);
+            }
+            spec.extend_help(&mut help);
+        }
+
+        // Basics
+        let mut output = format_header(help.title.as_str());
+        push_heading(&mut output, "USAGE");
+        push_line(&mut output, format!("    {}\n", help.usage).as_str());
 
-        // Display shortcuts
+        // Shortcuts
         for shortcut in shortcuts {
             let tmp_usage = help.usage.replace(cmd_alias, shortcut.as_str());
-            cli_sendln!(format!("    {}", tmp_usage).as_str());
+            push_line(&mut output, format!("    {}", tmp_usage).as_str());
         }
-        //cli_sendln!("");
 
         // Description
         if !help.description.is_empty() {
-            let options =
-                textwrap::Options::new(75).initial_indent("    ").subsequent_indent("    ");
+            let options = textwrap::Options::new(wrap_width())
+                .initial_indent("    ")
+                .subsequent_indent("    ");
             let desc = textwrap::fill(help.description.as_str(), &options);
 
-            cli_sendln!("DESCRIPTION:\n");
-            cli_sendln!(desc.as_str());
-            cli_sendln!("");
+            push_heading(&mut output, "DESCRIPTION:");
+            push_line(&mut output, desc.as_str());
+            push_line(&mut output, "");
         }
 
         // Parameters
         if !help.params.is_empty() {
-            cli_sendln!("PARAMETERS\n");
-            cli_display_array(&help.params);
+            push_heading(&mut output, "PARAMETERS");
+            output += &format_array(&help.params, Some("flag"));
         }
 
         // Flags
         if !help.flags.is_empty() {
-            cli_sendln!("FLAGS\n");
-            cli_display_array(&help.flags);
+            push_heading(&mut output, "FLAGS");
+            output += &format_array(&help.flags, Some("flag"));
         }
 
         // Examples
         if !help.examples.is_empty() {
-            cli_sendln!("EXAMPLES\n");
+            push_heading(&mut output, "EXAMPLES");
             for example in help.examples {
-                println!("    {}\n", example);
+                output += &format!("    {}\n\n", example);
             }
         }
 
         // End
-        cli_sendln!("-- END --\n");
+        push_line(&mut output, "-- END --\n");
+        output
     }
 
     /// Renders and displays the main help index for the application.
@@ -182,16 +274,42 @@ impl CliHelpScreen {
     ///
     /// * `router` - The CLI router containing all registered commands and categories
     pub fn render_index(router: &CliRouter) {
+        cli_write(CliStream::Stdout, &Self::format_index(router));
+    }
+
+    /// Renders the main help index for the application into a string.
+    ///
+    /// # Arguments
+    ///
+    /// * `router` - The CLI router containing all registered commands and categories
+    ///
+    /// # Returns
+    ///
+    /// Returns the help index exactly as `render_index()` would display it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliRouter, CliHelpScreen};
+    /// let mut router = CliRouter::new();
+    /// router.app_name("MyApp v1.0");
+    /// router.add_category("domain", "Domains", "Manage domain names");
+    ///
+    /// let output = CliHelpScreen::format_index(&router);
+    /// assert!(output.contains("-- MyApp v1.0\n"));
+    /// assert!(output.contains("    domain    Manage domain names\n"));
+    /// ```
+    pub fn format_index(router: &CliRouter) -> String {
         // Header
-        if router.app_name.is_empty() {
-            cli_header("Help");
+        let mut output = if router.app_name.is_empty() {
+            format_header("Help")
         } else {
-            cli_header(&router.app_name);
-        }
+            format_header(&router.app_name)
+        };
 
         // Globa flags, if we have them
         if !router.global_flags.is_empty() {
-            cli_sendln!("GLOBAL FLAGS\n");
+            push_heading(&mut output, "GLOBAL FLAGS");
             let mut global_arr = IndexMap::new();
             for gf in router.global_flags.iter() {
                 let mut key = format!("{}|{}", gf.short, gf.long);
@@ -201,13 +319,20 @@ impl CliHelpScreen {
                 if gf.long.is_empty() {
                     key = gf.short.to_string();
                 }
-                global_arr.insert(key, gf.desc.to_string());
+                let desc = match env_name(&router.env_prefix, &gf.env, &gf.long) {
+                    Some(env) => format!("{} [env: {}]", gf.desc, env),
+                    None => gf.desc.to_string(),
+                };
+                global_arr.insert(key, desc);
             }
-            cli_display_array(&global_arr);
+            output += &format_array(&global_arr, Some("flag"));
         }
 
-        cli_sendln!("AVAILABLE COMMANDS\n");
-        cli_sendln!("Run any of the commands with 'help' as the first argument for details\n");
+        push_heading(&mut output, "AVAILABLE COMMANDS");
+        push_line(
+            &mut output,
+            "Run any of the commands with 'help' as the first argument for details\n",
+        );
 
         // Display as needed
         let mut table: IndexMap<String, String> = indexmap![];
@@ -223,7 +348,7 @@ impl CliHelpScreen {
             }
 
             // Render array
-            cli_display_array(&table);
+            output += &format_array(&table, None);
 
         // No categories, display individual commands
         } else {
@@ -240,12 +365,11 @@ impl CliHelpScreen {
             }
 
             // Display commands
-            cli_display_array(&table);
+            output += &format_array(&table, None);
         }
 
-        // Exit
-        cli_sendln!("-- END --\r\n");
-        exit(0);
+        push_line(&mut output, "-- END --\r\n");
+        output
     }
 
     /// Renders and displays help for a specific category.
@@ -258,19 +382,49 @@ impl CliHelpScreen {
     ///
     /// * `router` - The CLI router containing all registered commands and categories
     /// * `cat_alias` - The alias/name of the category to display
-    pub fn render_category(router: &CliRouter, cat_alias: &String) {
-        // GEt category
-        let cat = router.categories.get(&cat_alias.to_string()).unwrap();
-        cli_header(&cat.title);
+    pub fn render_category(router: &CliRouter, cat_alias: &str) {
+        cli_write(CliStream::Stdout, &Self::format_category(router, cat_alias));
+    }
+
+    /// Renders help for a specific category into a string.
+    ///
+    /// # Arguments
+    ///
+    /// * `router` - The CLI router containing all registered commands and categories
+    /// * `cat_alias` - The alias/name of the category
+    ///
+    /// # Returns
+    ///
+    /// Returns the category's help exactly as `render_category()` would display it, or an
+    /// empty string if the category does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliRouter, CliHelpScreen};
+    /// let mut router = CliRouter::new();
+    /// router.add_category("domain", "Domains", "Manage domain names");
+    ///
+    /// let output = CliHelpScreen::format_category(&router, "domain");
+    /// assert!(output.starts_with("------------------------------\n-- Domains\n"));
+    /// assert!(CliHelpScreen::format_category(&router, "unknown").is_empty());
+    /// ```
+    pub fn format_category(router: &CliRouter, cat_alias: &str) -> String {
+        // Get category
+        let Some(cat) = router.categories.get(cat_alias) else {
+            return String::new();
+        };
+        let mut output = format_header(&cat.title);
 
         // Description
         if !cat.description.is_empty() {
-            let options =
-                textwrap::Options::new(75).initial_indent("    ").subsequent_indent("    ");
+            let options = textwrap::Options::new(wrap_width())
+                .initial_indent("    ")
+                .subsequent_indent("    ");
             let desc = textwrap::fill(cat.description.as_str(), &options);
 
-            cli_sendln!("DESCRIPTION:\n");
-            cli_sendln!(desc.as_str());
+            push_heading(&mut output, "DESCRIPTION:");
+            push_line(&mut output, desc.as_str());
         }
 
         // Sub categories
@@ -307,10 +461,22 @@ impl CliHelpScreen {
             table.insert(alias, cmd_help.description);
         }
 
-        // Display commands
-        cli_sendln!("AVAILABLE COMMANDS\n");
-        cli_display_array(&table);
-        cli_sendln!("-- END --\n");
-        std::process::exit(0);
+        // Commands
+        push_heading(&mut output, "AVAILABLE COMMANDS");
+        output += &format_array(&table, None);
+        push_line(&mut output, "-- END --\n");
+        output
     }
 }
+
+/// Appends a section title, followed by a blank line.
+fn push_heading(output: &mut String, title: &str) {
+    output.push_str(&paint(title, "header", CliStream::Stdout));
+    output.push_str("\n\n");
+}
+
+/// Appends a line of text with word wrapping, as `cli_sendln!` displays it.
+fn push_line(output: &mut String, text: &str) {
+    output.push_str(&format_wrapped(text));
+    output.push('\n');
+}
@@ -5,27 +5,57 @@
 // Apache License text: https://www.apache.org/licenses/LICENSE-2.0
 // MIT License text: https://opensource.org/licenses/MIT
 
-pub use self::error::CliError;
+pub use self::completions::CliShell;
+#[cfg(feature = "config")]
+pub use self::config::CliConfig;
+pub use self::error::{CliError, CliExitCodes};
 pub use self::help::CliHelpScreen;
+#[cfg(feature = "log")]
+pub use self::logger::{cli_init_logger, cli_set_log_file};
 pub use self::macros::*;
-pub use self::request::{CliFormat, CliRequest};
-pub use self::router::CliRouter;
+pub use self::output::{
+    CliBuffer, CliStream, cli_reset_output, cli_set_output, cli_set_width, cli_width, cli_write,
+};
+pub use self::repl::{cli_repl, cli_split_line};
+pub use self::request::{CliFormat, CliRequest, CliValueSource};
+pub use self::router::{CliRoute, CliRouter, CliTypoMode};
+pub use self::spec::{CliCommandSpec, CliFlagSpec, CliFromRequest, CliParamSpec, CliRun};
+pub use self::style::{CliColor, CliTheme, cli_escape, cli_set_color, cli_set_theme, cli_style};
 pub use anyhow;
+#[cfg(feature = "derive")]
+pub use falcon_cli_derive::CliCommand;
 pub use indexmap::{IndexMap, indexmap};
 
+use self::output::wrap_width;
+use self::style::paint;
+#[cfg(not(feature = "mock"))]
 use rpassword::read_password;
+use std::ffi::OsString;
 use std::fmt::Display;
 use std::hash::Hash;
-use std::process::{Command, exit};
+use std::io::IsTerminal;
+use std::process::{Command, ExitCode};
 use std::str::FromStr;
 use std::{env, fs};
 use zxcvbn::zxcvbn;
 
+mod completions;
+#[cfg(feature = "config")]
+mod config;
 pub mod error;
 mod help;
+#[cfg(feature = "log")]
+mod logger;
 pub mod macros;
+mod output;
+mod repl;
 mod request;
 mod router;
+mod spec;
+mod style;
+/// Helpers for testing commands with scripted input and captured output.
+#[cfg(feature = "mock")]
+pub mod testing;
 
 /// Trait that all CLI commands must implement.
 ///
@@ -68,6 +98,73 @@ pub trait CliCommand {
     /// This method should create and return a `CliHelpScreen` with information
     /// about how to use the command, including parameters, flags, and examples.
     fn help(&self) -> CliHelpScreen;
+
+    /// Returns the declarative specification of this command's parameters and flags.
+    ///
+    /// When provided, the router uses it to parse value flags, the request is validated
+    /// against it before `process()` is executed, and its parameters and flags are added
+    /// to the help screen.  See `CliCommandSpec` for details.  Defaults to `None`.
+    fn spec(&self) -> Option<CliCommandSpec> {
+        None
+    }
+
+    /// Returns tab completion suggestions for a parameter or flag value.
+    ///
+    /// Called by the shell completion scripts via the hidden `__complete` command.  The
+    /// request contains everything typed before the word being completed, and
+    /// `req.completing_flag` is set when the value of a flag is being completed, otherwise
+    /// the parameter at position `req.args.len()` is.  Suggestions should start with
+    /// `partial`.
+    ///
+    /// Defaults to an empty list, in which case values are completed from the formats within
+    /// `spec()` (`OneOf`, `File` and `Directory`).
+    ///
+    /// # Arguments
+    ///
+    /// * `req` - The request gathered from the words before the one being completed
+    /// * `partial` - The partially typed word being completed
+    fn complete(&self, _req: &CliRequest, _partial: &str) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Returns whether flags the command does not declare are accepted.
+    ///
+    /// By default, when the command declares its flags via its help screen or specification,
+    /// `cli_run()` rejects all other flags with `CliError::UnknownFlag`, with the value flags
+    /// given to `CliRouter::add()` also accepted.  Value flags alone do not declare the full
+    /// set of flags, so are not checked.  Commands that accept arbitrary flags, such as those
+    /// passing them on to another program, should return `true`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use falcon_cli::{CliCommand, CliHelpScreen, CliRequest, CliRouter, cli_run_with};
+    /// use std::process::ExitCode;
+    ///
+    /// #[derive(Default)]
+    /// struct CreateDomain;
+    /// impl CliCommand for CreateDomain {
+    ///     fn process(&self, _req: &CliRequest) -> anyhow::Result<()> { Ok(()) }
+    ///     fn help(&self) -> CliHelpScreen {
+    ///         let mut help = CliHelpScreen::new("Create Domain", "domain create <NAME>", "");
+    ///         help.add_flag("--ip-address", "IP address to point the domain to");
+    ///         help
+    ///     }
+    /// }
+    ///
+    /// let mut router = CliRouter::new();
+    /// router.add::<CreateDomain>("domain create", vec![], vec!["--ip-address"]);
+    ///
+    /// // ERROR: Unknown flag --ip-adress, did you mean --ip-address?
+    /// let code = cli_run_with(&mut router, ["domain", "create", "a.com", "--ip-adress", "1.2.3.4"]);
+    /// assert_eq!(code, ExitCode::from(64));
+    ///
+    /// let code = cli_run_with(&mut router, ["domain", "create", "a.com", "--ip-address", "1.2.3.4"]);
+    /// assert_eq!(code, ExitCode::SUCCESS);
+    /// ```
+    fn allow_unknown_flags(&self) -> bool {
+        false
+    }
 }
 
 /// Executes the CLI command router and processes the appropriate command.
@@ -76,36 +173,188 @@ pub trait CliCommand {
 /// `router.add()`. It will parse command line arguments, look up the appropriate
 /// command handler, and execute it or display help as needed.
 ///
+/// If the command fails, the error is written to STDERR and the exit code for the
+/// error is returned as defined by the router's `CliExitCodes`.
+///
 /// # Arguments
 ///
 /// * `router` - A mutable reference to the configured CLI router
 ///
+/// # Returns
+///
+/// Returns the `ExitCode` the process should exit with.
+///
 /// # Example
 ///
 /// ```no_run
 /// use falcon_cli::{CliRouter, cli_run};
+/// use std::process::ExitCode;
+///
+/// fn main() -> ExitCode {
+///     let mut router = CliRouter::new();
+///     router.app_name("My App");
+///     // Add commands here...
+///     cli_run(&mut router)
+/// }
+/// ```
+pub fn cli_run(router: &mut CliRouter) -> ExitCode {
+    cli_run_with(router, env::args_os().skip(1))
+}
+
+/// Executes the CLI command router against the given arguments.
+///
+/// Identical to `cli_run()`, except the arguments are taken from the provided iterator
+/// instead of the process command line.  Useful for embedding the router within a larger
+/// program that has its own argv, or for driving it from tests.
+///
+/// # Arguments
+///
+/// * `router` - A mutable reference to the configured CLI router
+/// * `args` - The arguments to route, excluding the program name
+///
+/// # Returns
+///
+/// Returns the `ExitCode` the process should exit with.
+///
+/// # Example
+///
+/// ```no_run
+/// use falcon_cli::{CliRouter, cli_run_with};
 ///
 /// let mut router = CliRouter::new();
-/// router.app_name("My App");
 /// // Add commands here...
-/// cli_run(&mut router);
+/// cli_run_with(&mut router, vec!["domain", "create", "example.com"]);
 /// ```
-pub fn cli_run(router: &mut CliRouter) {
+pub fn cli_run_with<I>(router: &mut CliRouter, args: I) -> ExitCode
+where
+    I: IntoIterator,
+    I::Item: Into<OsString>,
+{
+    ExitCode::from(run_args(router, args))
+}
+
+/// Routes and executes the arguments, returning the exit code.
+pub(crate) fn run_args<I>(router: &mut CliRouter, args: I) -> u8
+where
+    I: IntoIterator,
+    I::Item: Into<OsString>,
+{
+    // Hidden entry point called by the shell completion scripts
+    let args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+    if args.first().is_some_and(|arg| arg == "__complete") {
+        let words: Vec<String> =
+            args[1..].iter().map(|arg| arg.to_string_lossy().to_string()).collect();
+        for suggestion in router.complete(&words) {
+            cli_write(CliStream::Stdout, &format!("{}\n", suggestion));
+        }
+        return 0;
+    }
+
+    let args = router.parse_args(args);
+    run_parsed(router, args)
+}
+
+/// Routes and executes arguments whose global flags have already been parsed, returning the
+/// exit code.
+pub(crate) fn run_parsed(router: &mut CliRouter, args: Option<Vec<String>>) -> u8 {
+    let exit_codes = router.exit_codes;
+    let has_completions = router.completions_enabled;
+
     // Lookup route
-    let (req, cmd) = match router.lookup() {
-        Some(r) => r,
-        None => {
-            CliHelpScreen::render_index(&router);
-            exit(0);
+    let mut req = match router.route_args(args) {
+        CliRoute::Command(req, _) => req,
+        CliRoute::Help(Some(cat_alias)) => {
+            CliHelpScreen::render_category(router, &cat_alias);
+            return 0;
+        }
+        CliRoute::Help(None) => {
+            CliHelpScreen::render_index(router);
+            return 0;
+        }
+        CliRoute::NotFound => {
+            CliHelpScreen::render_index(router);
+            return exit_codes.not_found;
+        }
+        CliRoute::Error(e) => {
+            cli_error_line(&e);
+            return exit_codes.get(&e.into());
+        }
+        CliRoute::Version(msg) => {
+            cli_write(CliStream::Stdout, &format!("{}\n", msg));
+            return 0;
+        }
+        CliRoute::Suggestions(suggestions) => {
+            let mut message = "No command with that name exists.  Did you mean:".to_string();
+            for alias in suggestions {
+                message += &format!("\n    {}", alias);
+            }
+            cli_error_line(&message);
+            return exit_codes.not_found;
+        }
+        CliRoute::Ambiguous(candidates) => {
+            let mut message = "Ambiguous command, which matches:".to_string();
+            for alias in candidates {
+                message += &format!("\n    {}", alias);
+            }
+            cli_error_line(&message);
+            return exit_codes.not_found;
         }
     };
 
     // Process as needed
+    let cmd = router.commands[&req.cmd_alias].as_ref();
     if req.is_help {
-        CliHelpScreen::render(&cmd, &req.cmd_alias, &req.shortcuts);
-    } else if let Err(e) = cmd.process(&req) {
-        cli_send!("ERROR: {}\n", e);
+        cli_write(
+            CliStream::Stdout,
+            &CliHelpScreen::format(router, &req.cmd_alias),
+        );
+    } else if has_completions && req.cmd_alias == "completions" {
+        let shell = match req.args.first().map(|s| s.parse::<CliShell>()) {
+            Some(Ok(shell)) => shell,
+            Some(Err(e)) => {
+                cli_error_line(&e);
+                return exit_codes.invalid_param;
+            }
+            None => {
+                cli_error_line(&CliError::MissingParams);
+                return exit_codes.missing_params;
+            }
+        };
+        let mut script = Vec::new();
+        if let Err(e) = router.generate_completions(shell, &mut script) {
+            cli_error_line(&e);
+            return exit_codes.other;
+        }
+        cli_write(CliStream::Stdout, &String::from_utf8_lossy(&script));
+    } else if let Err(e) = router
+        .check_flags(cmd, &req)
+        .map_err(anyhow::Error::from)
+        .and_then(|_| run_command(cmd, &mut req, router))
+    {
+        cli_error_line(&e);
+        return exit_codes.get(&e);
     }
+
+    0
+}
+
+/// Writes an error to the error output stream.
+pub(crate) fn cli_error_line(err: &dyn Display) {
+    let line = paint(&format!("ERROR: {}", err), "error", CliStream::Stderr);
+    cli_write(CliStream::Stderr, &format!("{}\n", line));
+}
+
+/// Validates the request against the command's specification, if it has one, and processes it.
+///
+/// Value flags that were not given fall back to their environment variables and configured
+/// values, as per the router.
+fn run_command(
+    cmd: &dyn CliCommand,
+    req: &mut CliRequest,
+    router: &CliRouter,
+) -> anyhow::Result<()> {
+    router.command_spec(cmd, &req.cmd_alias).apply(req)?;
+    cmd.process(req)
 }
 
 /// Displays a formatted header in the terminal.
@@ -128,9 +377,16 @@ pub fn cli_run(router: &mut CliRouter) {
 /// // ------------------------------
 /// ```
 pub fn cli_header(text: &str) {
-    println!("------------------------------");
-    println!("-- {}", text);
-    println!("------------------------------\n");
+    cli_write(CliStream::Stdout, &format_header(text));
+}
+
+/// Formats a header, as displayed by `cli_header()`.
+pub(crate) fn format_header(text: &str) -> String {
+    let header = format!(
+        "------------------------------\n-- {}\n------------------------------",
+        text
+    );
+    format!("{}\n\n", paint(&header, "header", CliStream::Stdout))
 }
 
 /// Prompts the user to select an option from a list.
@@ -176,21 +432,17 @@ where
     cli_send!("\nSelect One: ");
 
     // Get user input
-    let mut input: String;
     loop {
-        input = String::new();
-
-        io::stdin().read_line(&mut input).expect("Failed to read line");
+        let input = read_input_line();
         let input = input.trim();
 
-        if let Ok(value) = input.parse::<K>() {
-            if options.contains_key(&value) {
-                return value;
-            }
+        if let Ok(value) = input.parse::<K>()
+            && options.contains_key(&value)
+        {
+            return value;
         }
 
-        print!("\r\nInvalid option, try again: ");
-        io::stdout().flush().unwrap();
+        cli_write(CliStream::Stdout, "\r\nInvalid option, try again: ");
     }
 }
 
@@ -219,11 +471,9 @@ where
 pub fn cli_get_input(message: &str, default_value: &str) -> String {
     // Display message
     cli_send!(message);
-    io::stdout().flush().unwrap();
 
     // Get user input
-    let mut input = String::new();
-    io::stdin().read_line(&mut input).expect("Failed to read line");
+    let input = read_input_line();
     let mut input = input.trim();
 
     // Default value, if needed
@@ -258,13 +508,11 @@ pub fn cli_get_input(message: &str, default_value: &str) -> String {
 pub fn cli_get_multiline_input(message: &str) -> String {
     // Display message
     cli_send!(&format!("{} (empty line to stop)\n\n", message));
-    io::stdout().flush().unwrap();
 
     // Get user input
     let mut res: Vec<String> = Vec::new();
     loop {
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).expect("Failed to read line");
+        let input = read_input_line();
         let input = input.trim();
 
         if input.is_empty() {
@@ -308,9 +556,7 @@ pub fn cli_confirm(message: &str) -> bool {
     // Get user input
     let mut _input = "".to_string();
     loop {
-        _input = String::new();
-
-        io::stdin().read_line(&mut _input).expect("Failed to read line");
+        _input = read_input_line();
         let _input = _input.trim().to_lowercase();
 
         if _input != "y" && _input != "n" {
@@ -348,7 +594,7 @@ pub fn cli_confirm(message: &str) -> bool {
 /// let password = cli_get_password("Enter password: ", false);
 /// println!("Password entered successfully");
 /// ```
-#[cfg(not(feature="mock"))]
+#[cfg(not(feature = "mock"))]
 pub fn cli_get_password(message: &str, allow_blank: bool) -> String {
     // Get message
     let password_message = if message.is_empty() {
@@ -373,7 +619,7 @@ pub fn cli_get_password(message: &str, allow_blank: bool) -> String {
     _password
 }
 
-#[cfg(feature="mock")]
+#[cfg(feature = "mock")]
 pub fn cli_get_password(message: &str, allow_blank: bool) -> String {
     cli_get_input(message, if allow_blank { "" } else { "password" })
 }
@@ -480,7 +726,8 @@ pub fn cli_get_new_password(req_strength: u8) -> String {
 ///
 /// Renders data in a tabular format similar to SQL database output, with borders
 /// and properly aligned columns. Column widths are automatically calculated based
-/// on the content.
+/// on the content, with the widest columns wrapped onto multiple lines as needed to
+/// fit within the terminal width.
 ///
 /// # Arguments
 ///
@@ -504,64 +751,84 @@ pub fn cli_get_new_password(req_strength: u8) -> String {
 pub fn cli_display_table<C: Display, R: Display>(columns: &[C], rows: &[Vec<R>]) {
     // Return if no rows
     if rows.is_empty() {
-        println!("No rows to display.\n");
+        cli_write(CliStream::Stdout, "No rows to display.\n\n");
         return;
     }
 
-    // Initialize sizes - using index-based approach since we can't use T as HashMap key
-    let mut sizes: Vec<usize> = vec![0; columns.len()];
-
-    // Get sizes of column headers
-    for (i, col) in columns.iter().enumerate() {
-        let col_str = col.to_string();
-        sizes[i] = col_str.len();
-    }
-
-    // Get maximum sizes by checking all row values
-    for row in rows {
+    // Get sizes of column headers and values
+    let columns: Vec<String> = columns.iter().map(|col| col.to_string()).collect();
+    let rows: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| row.iter().take(columns.len()).map(|val| val.to_string()).collect())
+        .collect();
+    let mut sizes: Vec<usize> = columns.iter().map(|col| col.len()).collect();
+    for row in rows.iter() {
         for (i, val) in row.iter().enumerate() {
-            if i < sizes.len() {
-                let val_str = val.to_string();
-                let val_len = val_str.len();
-                if val_len > sizes[i] {
-                    sizes[i] = val_len;
-                }
-            }
+            sizes[i] = sizes[i].max(val.len());
         }
     }
 
-    // Add padding to all column sizes
-    for size in sizes.iter_mut() {
-        *size += 3;
+    // Shrink the widest columns until the table fits within the terminal
+    let max_width = cli_width();
+    while 1 + sizes.iter().map(|size| size + 5).sum::<usize>() > max_width {
+        let Some((i, _)) =
+            sizes.iter().enumerate().filter(|(_, size)| **size > 8).max_by_key(|(_, size)| **size)
+        else {
+            break;
+        };
+        sizes[i] -= 1;
     }
 
-    // Initialize header variables
+    // Display the column headers
     let mut header = String::from("+");
-    let mut col_header = String::from("|");
-
-    // Print column headers
-    for (i, col) in columns.iter().enumerate() {
-        let col_str = col.to_string();
-        let padded_col = format!("{}{}", col_str, " ".repeat(sizes[i] - col_str.len()));
-        header += &("-".repeat(sizes[i] + 1) + "+");
-        col_header += &format!(" {}|", padded_col);
+    for size in sizes.iter() {
+        header += &("-".repeat(size + 4) + "+");
     }
-
-    println!("{}\n{}\n{}", header, col_header, header);
+    let mut output = format!(
+        "{}\n{}{}\n",
+        header,
+        format_table_row(&columns, &sizes),
+        header
+    );
 
     // Display the rows
-    for row in rows {
-        let mut line = String::from("|");
-        for (i, val) in row.iter().enumerate() {
-            if i < sizes.len() {
-                let val_str = val.to_string();
-                let padded_val = format!(" {}{}", val_str, " ".repeat(sizes[i] - val_str.len()));
-                line += &format!("{}|", padded_val);
+    for row in rows.iter() {
+        output += &format_table_row(row, &sizes);
+    }
+    output += &format!("{}\n\n", header);
+    cli_write(CliStream::Stdout, &output);
+}
+
+/// Formats a row of a table, wrapping any values wider than their column onto multiple lines.
+fn format_table_row(cells: &[String], sizes: &[usize]) -> String {
+    let wrapped: Vec<Vec<String>> = sizes
+        .iter()
+        .enumerate()
+        .map(|(i, size)| {
+            let cell = cells.get(i).map(|cell| cell.as_str()).unwrap_or("");
+            if cell.len() <= *size {
+                vec![cell.to_string()]
+            } else {
+                textwrap::wrap(cell, *size).into_iter().map(|line| line.to_string()).collect()
             }
+        })
+        .collect();
+    let height = wrapped.iter().map(|lines| lines.len()).max().unwrap_or(1);
+
+    let mut output = String::new();
+    for line in 0..height {
+        output.push('|');
+        for (i, size) in sizes.iter().enumerate() {
+            let text = wrapped[i].get(line).map(|text| text.as_str()).unwrap_or("");
+            output += &format!(
+                " {}{}|",
+                text,
+                " ".repeat((size + 3).saturating_sub(text.len()))
+            );
         }
-        println!("{}", line);
+        output.push('\n');
     }
-    println!("{}\n", header);
+    output
 }
 
 /// Displays a two-column array with proper spacing and word wrapping.
@@ -589,6 +856,15 @@ pub fn cli_display_table<C: Display, R: Display>(columns: &[C], rows: &[Vec<R>])
 /// cli_display_array(&items);
 /// ```
 pub fn cli_display_array<K: Display, V: Display>(rows: &IndexMap<K, V>) {
+    cli_write(CliStream::Stdout, &format_array(rows, None));
+}
+
+/// Formats a two column array, as displayed by `cli_display_array()`, optionally applying
+/// one of the theme's styles to the left column.
+pub(crate) fn format_array<K: Display, V: Display>(
+    rows: &IndexMap<K, V>,
+    key_style: Option<&str>,
+) -> String {
     // Get max left column size
     let mut size = 0;
     for key in rows.keys() {
@@ -598,19 +874,30 @@ pub fn cli_display_array<K: Display, V: Display>(rows: &IndexMap<K, V>) {
         }
     }
     let indent = " ".repeat(size);
-    let indent_size = size - 4;
+    let indent_size = size.saturating_sub(4);
 
     // Go through rows
+    let mut output = String::new();
     for (key, value) in rows {
         let key_str = key.to_string();
         let value_str = value.to_string();
-        let left_col = format!("    {}{}", key_str, " ".repeat(indent_size - key_str.len()));
-        let options =
-            textwrap::Options::new(75).initial_indent(&left_col).subsequent_indent(&indent);
-        let line = textwrap::fill(&value_str, &options);
-        println!("{}", line);
+        let padding = " ".repeat(indent_size - key_str.len());
+        let left_col = match key_style {
+            Some(style) => format!(
+                "    {}{}",
+                paint(&key_str, style, CliStream::Stdout),
+                padding
+            ),
+            None => format!("    {}{}", key_str, padding),
+        };
+        let options = textwrap::Options::new(wrap_width())
+            .initial_indent(&left_col)
+            .subsequent_indent(&indent);
+        output += &textwrap::fill(&value_str, &options);
+        output.push('\n');
     }
-    println!("");
+    output.push('\n');
+    output
 }
 
 /// Clears the terminal screen.
@@ -626,7 +913,7 @@ pub fn cli_display_array<K: Display, V: Display>(rows: &IndexMap<K, V>) {
 /// println!("Screen cleared!");
 /// ```
 pub fn cli_clear_screen() {
-    print!("\x1B[2J");
+    cli_write(CliStream::Stdout, "\x1B[2J");
 }
 
 /// Opens a text editor for the user to edit content.
@@ -657,6 +944,11 @@ pub fn cli_clear_screen() {
 /// }
 /// ```
 pub fn cli_text_editor(contents: &str) -> Result<String, CliError> {
+    #[cfg(feature = "mock")]
+    if let Some(answer) = testing::next_answer() {
+        return Ok(answer);
+    }
+
     // Create temp file
     let temp_dir = env::temp_dir();
     let temp_file = temp_dir.join(format!(
@@ -674,7 +966,7 @@ pub fn cli_text_editor(contents: &str) -> Result<String, CliError> {
     // Launch editor
     let status = if cfg!(target_os = "windows") {
         Command::new("cmd")
-            .args(&["/C", &format!("{} \"{}\"", editor, temp_file.display())])
+            .args(["/C", &format!("{} \"{}\"", editor, temp_file.display())])
             .status()
     } else {
         Command::new(&editor).arg(&temp_file).status()
@@ -701,6 +993,29 @@ pub fn cli_text_editor(contents: &str) -> Result<String, CliError> {
     }
 }
 
+/// Checks whether the user can be prompted for input, either via a terminal or scripted
+/// answers.
+pub(crate) fn stdin_is_interactive() -> bool {
+    #[cfg(feature = "mock")]
+    if testing::has_answers() {
+        return true;
+    }
+
+    io::stdin().is_terminal()
+}
+
+/// Reads a line of input from STDIN, or the next scripted answer when testing.
+fn read_input_line() -> String {
+    #[cfg(feature = "mock")]
+    if let Some(answer) = testing::next_answer() {
+        return answer;
+    }
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).expect("Failed to read line");
+    input
+}
+
 /// Determines the text editor to use based on environment variables and platform.
 ///
 /// Checks environment variables in order of preference (`VISUAL`, then `EDITOR`),
@@ -855,7 +1170,7 @@ impl CliProgressBar {
     pub fn finish(&mut self) {
         self.value = self.total;
         self.render();
-        println!("");
+        cli_write(CliStream::Stdout, "\n");
     }
 
     /// Renders the progress bar to the terminal.
@@ -863,11 +1178,7 @@ impl CliProgressBar {
     /// Internal method that calculates and displays the progress bar with
     /// percentage, message, and visual indicator.
     fn render(&self) {
-        let percent = if self.total > 0 {
-            (self.value * 100) / self.total
-        } else {
-            0
-        };
+        let percent = (self.value * 100).checked_div(self.total).unwrap_or(0);
 
         // Calculate available space
         // Format: [ xx% ] <MESSAGE> [******      ]
@@ -877,7 +1188,7 @@ impl CliProgressBar {
         let fixed_overhead = 8 + percent_str.len();
 
         // Available space for message and bar
-        let available = 75_usize.saturating_sub(fixed_overhead);
+        let available = wrap_width().saturating_sub(fixed_overhead);
 
         // Reserve minimum 10 chars for bar (including brackets)
         let bar_size = 10;
@@ -899,12 +1210,14 @@ impl CliProgressBar {
         let bar = format!("{}{}", "*".repeat(filled), " ".repeat(empty));
 
         // Print with carriage return to overwrite line
-        print!("\r[ {}% ] {} [{}]", percent, display_message, bar);
-        io::stdout().flush().unwrap();
+        cli_write(
+            CliStream::Stdout,
+            &format!("\r[ {}% ] {} [{}]", percent, display_message, bar),
+        );
 
         // Print newline when complete
         if self.value >= self.total {
-            println!();
+            cli_write(CliStream::Stdout, "\n");
         }
     }
 }
@@ -0,0 +1,169 @@
+// Copyright 2025 Aquila Labs of Alberta, Canada <matt@cicero.sh>
+// Licensed under either the Apache License, Version 2.0 OR the MIT License, at your option.
+// You may not use this file except in compliance with one of the Licenses.
+// Apache License text: https://www.apache.org/licenses/LICENSE-2.0
+// MIT License text: https://opensource.org/licenses/MIT
+
+use crate::macros::{CliLevel, cli_set_verbosity, cli_verbosity, write_message};
+use crate::style::strip_markup;
+use log::{Level, Log, Metadata, Record, SetLoggerError};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+static LOGGER: CliLogger = CliLogger;
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+static LOG_FILE: Mutex<Option<File>> = Mutex::new(None);
+
+/// Logger for the `log` crate that displays records the same as the output macros.
+struct CliLogger;
+
+impl Log for CliLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        let level = cli_level(record.level());
+        if !self.enabled(record.metadata()) || level.verbosity() > cli_verbosity() {
+            return;
+        }
+
+        let message = record.args().to_string();
+        write_message(level, &message);
+        write_file(record.level(), record.target(), &message);
+    }
+
+    fn flush(&self) {
+        if let Some(file) = LOG_FILE.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// Installs falcon-cli as the logger of the `log` crate.
+///
+/// Records logged by any crate via `log::info!` and similar are then displayed the same as
+/// the output macros, styled as per the theme and filtered by the verbosity set via
+/// `cli_set_verbosity()`, and written to the log file set via `cli_set_log_file()`.
+///
+/// # Returns
+///
+/// Returns an error if a logger has already been installed.
+///
+/// # Example
+///
+/// ```
+/// use falcon_cli::cli_init_logger;
+///
+/// cli_init_logger().unwrap();
+/// log::info!("Displayed via falcon-cli");
+/// ```
+pub fn cli_init_logger() -> Result<(), SetLoggerError> {
+    log::set_logger(&LOGGER)?;
+    INSTALLED.store(true, Ordering::Relaxed);
+    cli_set_verbosity(cli_verbosity());
+    Ok(())
+}
+
+/// Sets a file that all displayed log messages are also written to.
+///
+/// Messages from the output macros other than `cli_send!` and `cli_sendln!` are appended to
+/// the file with a timestamp and level, along with records from the `log` crate if
+/// `cli_init_logger()` was called.  Markup is removed.
+///
+/// # Arguments
+///
+/// * `path` - Location of the log file, created if it does not exist
+///
+/// # Returns
+///
+/// Returns an error if the file could not be opened.
+///
+/// # Example
+///
+/// ```no_run
+/// use falcon_cli::{cli_info, cli_set_log_file};
+///
+/// cli_set_log_file("/var/log/myapp.log").unwrap();
+/// cli_info!("Deployment started");
+/// // 2025-01-31T14:05:09Z [INFO] Deployment started
+/// ```
+pub fn cli_set_log_file(path: &str) -> io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    *LOG_FILE.lock().unwrap_or_else(|e| e.into_inner()) = Some(file);
+    Ok(())
+}
+
+/// Records a message from the output macros within the log file, and forwards it to the
+/// `log` crate unless falcon-cli is the installed logger, as it was already displayed.
+pub(crate) fn record(level: CliLevel, text: &str) {
+    let level = match level {
+        CliLevel::Send | CliLevel::SendLn => return,
+        CliLevel::Info => Level::Info,
+        CliLevel::Warn => Level::Warn,
+        CliLevel::Error => Level::Error,
+        CliLevel::Debug => Level::Debug,
+        CliLevel::Trace => Level::Trace,
+    };
+
+    let message = strip_markup(text);
+    write_file(level, "", &message);
+    if !INSTALLED.load(Ordering::Relaxed) {
+        log::log!(level, "{}", message);
+    }
+}
+
+fn cli_level(level: Level) -> CliLevel {
+    match level {
+        Level::Error => CliLevel::Error,
+        Level::Warn => CliLevel::Warn,
+        Level::Info => CliLevel::Info,
+        Level::Debug => CliLevel::Debug,
+        Level::Trace => CliLevel::Trace,
+    }
+}
+
+/// Appends a line to the log file, if one was set.
+fn write_file(level: Level, target: &str, message: &str) {
+    let mut file = LOG_FILE.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(file) = file.as_mut() else {
+        return;
+    };
+
+    let source = if target.is_empty() {
+        level.to_string()
+    } else {
+        format!("{} {}", level, target)
+    };
+    let _ = writeln!(file, "{} [{}] {}", timestamp(), source, message);
+}
+
+/// Gets the current UTC time formatted as per RFC 3339, such as `2025-01-31T14:05:09Z`.
+fn timestamp() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, time) = (secs / 86400, secs % 86400);
+
+    // Convert days since epoch to a civil date
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        (time % 3600) / 60,
+        time % 60
+    )
+}
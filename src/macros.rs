@@ -4,7 +4,11 @@
 // Apache License text: https://www.apache.org/licenses/LICENSE-2.0
 // MIT License text: https://opensource.org/licenses/MIT
 
+use crate::output::{CliStream, cli_write, wrap_width};
+use crate::style::{paint, render_markup};
+use std::fmt;
 pub use std::io::{self, Write};
+use std::sync::atomic::{AtomicU8, Ordering};
 pub use textwrap::Options as Textwrap_Options;
 pub use textwrap::fill as Textwrap_Fill;
 
@@ -24,79 +28,158 @@ pub enum CliLevel {
     Warn,
     /// Error message.
     Error,
-    /// Debug message (requires `log` feature).
+    /// Debug message, only displayed with `CliVerbosity::Verbose` or higher.
     Debug,
-    /// Trace message (requires `log` feature).
+    /// Trace message, only displayed with `CliVerbosity::Trace`.
     Trace,
 }
 
-/// Core logging function used by CLI output macros.
+impl CliLevel {
+    /// Gets the lowest verbosity at which messages of this level are displayed.
+    pub(crate) fn verbosity(self) -> CliVerbosity {
+        match self {
+            CliLevel::Send | CliLevel::SendLn | CliLevel::Warn | CliLevel::Error => {
+                CliVerbosity::Quiet
+            }
+            CliLevel::Info => CliVerbosity::Normal,
+            CliLevel::Debug => CliVerbosity::Verbose,
+            CliLevel::Trace => CliVerbosity::Trace,
+        }
+    }
+}
+
+/// Verbosity thresholds controlling which messages are displayed.
+///
+/// Output via `cli_send!` and `cli_sendln!`, warnings and errors are always displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum CliVerbosity {
+    /// Only warnings and errors.
+    Quiet,
+    /// Informational messages, warnings and errors.
+    #[default]
+    Normal,
+    /// Debug messages as well.
+    Verbose,
+    /// Debug and trace messages as well.
+    Trace,
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(CliVerbosity::Normal as u8);
+
+/// Sets which messages are displayed by the output macros.
 ///
-/// Formats the text with provided arguments, applies word wrapping, and outputs
-/// according to the specified log level. When the `log` feature is enabled, also
-/// logs to the configured logging backend.
+/// Defaults to `CliVerbosity::Normal`, which hides debug and trace messages.  When the `log`
+/// feature is enabled, also sets the maximum level of the `log` crate to match.
 ///
 /// # Arguments
 ///
-/// * `level` - The log level determining output behavior
-/// * `text` - The text to output (may contain `{}` placeholders)
-/// * `args` - Arguments to replace placeholders in the text
-pub fn cli_log(level: CliLevel, text: &str, args: &[String]) {
-    let wrapped = format_wrapped(text, args, None);
-
-    match level {
-        CliLevel::Send => {
-            print!("{}", wrapped);
-        }
-        CliLevel::SendLn => {
-            println!("{}", wrapped);
-        }
+/// * `verbosity` - The verbosity threshold
+///
+/// # Example
+///
+/// ```
+/// use falcon_cli::{CliBuffer, CliStream, CliVerbosity, cli_debug, cli_set_output, cli_set_verbosity};
+///
+/// let buffer = CliBuffer::new();
+/// cli_set_output(CliStream::Stdout, buffer.clone());
+///
+/// cli_debug!("Hidden");
+/// cli_set_verbosity(CliVerbosity::Verbose);
+/// cli_debug!("Displayed");
+///
+/// assert_eq!(buffer.contents(), "Displayed\n");
+/// ```
+pub fn cli_set_verbosity(verbosity: CliVerbosity) {
+    VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
 
-        _other => {
-            #[cfg(feature = "log")]
-            match other {
-                CliLevel::Info => log::info!("{}", text),
-                CliLevel::Warn => log::warn!("{}", text),
-                CliLevel::Error => log::error!("{}", text),
-                CliLevel::Debug => log::debug!("{}", text),
-                CliLevel::Trace => log::trace!("{}", text),
-                _ => {}
-            }
-            println!("{}", wrapped);
-        }
-    }
+    #[cfg(feature = "log")]
+    log::set_max_level(match verbosity {
+        CliVerbosity::Quiet => log::LevelFilter::Warn,
+        CliVerbosity::Normal => log::LevelFilter::Info,
+        CliVerbosity::Verbose => log::LevelFilter::Debug,
+        CliVerbosity::Trace => log::LevelFilter::Trace,
+    });
+}
 
-    io::stdout().flush().unwrap();
+/// Gets the current verbosity threshold.
+pub fn cli_verbosity() -> CliVerbosity {
+    match VERBOSITY.load(Ordering::Relaxed) {
+        0 => CliVerbosity::Quiet,
+        1 => CliVerbosity::Normal,
+        2 => CliVerbosity::Verbose,
+        _ => CliVerbosity::Trace,
+    }
 }
 
-/// Formats text with argument substitution and word wrapping.
+/// Core logging function used by CLI output macros.
+///
+/// Applies word wrapping to the formatted text, and outputs according to the specified
+/// log level.  Warnings and errors are written to the
+/// `CliStream::Stderr` stream, and everything else to `CliStream::Stdout`. When the `log` feature is enabled, also
+/// logs to the configured logging backend.
 ///
-/// Replaces `{}` placeholders in the text with arguments, optionally adds a prefix,
-/// and applies word wrapping at 75 characters.
+/// Markup within the text, such as `[bold]Done[/]`, is converted as per `cli_style()`, and
+/// warnings and errors are styled as per the theme.  Messages below the verbosity set via
+/// `cli_set_verbosity()` are skipped.
 ///
 /// # Arguments
 ///
-/// * `text` - The text to format (may contain `{}` placeholders)
-/// * `args` - Arguments to replace placeholders
-/// * `prefix` - Optional prefix to prepend to the text
-fn format_wrapped(text: &str, args: &[String], prefix: Option<&str>) -> String {
-    // Replace placeholders
-    let mut text = text.to_string();
-    for arg in args {
-        text = text.replacen("{}", arg, 1);
+/// * `level` - The log level determining output behavior
+/// * `args` - The formatted text to output, as created by `format_args!`
+///
+/// # Example
+///
+/// ```
+/// use falcon_cli::{CliBuffer, CliLevel, CliStream, cli_log, cli_reset_output, cli_set_output};
+///
+/// let buffer = CliBuffer::new();
+/// cli_set_output(CliStream::Stdout, buffer.clone());
+/// cli_log(CliLevel::SendLn, format_args!("{:>4} of {} files in {:?}", 3, 10, "a{}b"));
+/// cli_reset_output();
+///
+/// assert_eq!(buffer.contents(), "   3 of 10 files in \"a{}b\"\n");
+/// ```
+pub fn cli_log(level: CliLevel, args: fmt::Arguments<'_>) {
+    if level.verbosity() > cli_verbosity() {
+        return;
     }
 
-    if let Some(prefix) = prefix {
-        text = format!("{}{}", prefix, text);
-    }
+    let text = match args.as_str() {
+        Some(text) => text.to_string(),
+        None => args.to_string(),
+    };
+    write_message(level, &text);
 
-    // Word wrap
-    Textwrap_Fill(&text, Textwrap_Options::new(75))
+    #[cfg(feature = "log")]
+    crate::logger::record(level, &text);
+}
+
+/// Displays a message according to its level, without forwarding it to the `log` crate.
+pub(crate) fn write_message(level: CliLevel, text: &str) {
+    let stream = match level {
+        CliLevel::Warn | CliLevel::Error => CliStream::Stderr,
+        _ => CliStream::Stdout,
+    };
+    let wrapped = format_wrapped(&render_markup(text, stream));
+
+    let output = match level {
+        CliLevel::Send => wrapped,
+        CliLevel::Warn => format!("{}\n", paint(&wrapped, "warning", stream)),
+        CliLevel::Error => format!("{}\n", paint(&wrapped, "error", stream)),
+        _ => format!("{}\n", wrapped),
+    };
+    cli_write(stream, &output);
+}
+
+/// Applies word wrapping to the terminal width.
+pub(crate) fn format_wrapped(text: &str) -> String {
+    Textwrap_Fill(text, Textwrap_Options::new(wrap_width()))
 }
 
 /// Outputs text without a newline.
 ///
-/// Similar to `print!`, but with word wrapping. Supports format-style arguments.
+/// Similar to `print!`, but with word wrapping.  Supports the same format strings as
+/// `format!`, or a single value to display as is.
 ///
 /// # Example
 ///
@@ -108,17 +191,18 @@ fn format_wrapped(text: &str, args: &[String], prefix: Option<&str>) -> String {
 /// ```
 #[macro_export]
 macro_rules! cli_send {
-    ($text:expr) => { $crate::cli_log($crate::CliLevel::Send, $text, &[]) };
-    ($text:expr, $( $arg:expr ),*) => {{
-        let mut args = vec![];
-        $( args.push($arg.to_string()); )*
-        $crate::cli_log($crate::CliLevel::Send, $text, &args)
-    }};
+    ($fmt:literal $($arg:tt)*) => {
+        $crate::cli_log($crate::CliLevel::Send, format_args!($fmt $($arg)*))
+    };
+    ($text:expr) => {
+        $crate::cli_log($crate::CliLevel::Send, format_args!("{}", $text))
+    };
 }
 
 /// Outputs text with a newline.
 ///
-/// Similar to `println!`, but with word wrapping. Supports format-style arguments.
+/// Similar to `println!`, but with word wrapping.  Supports the same format strings as
+/// `format!`, or a single value to display as is.
 ///
 /// # Example
 ///
@@ -127,20 +211,28 @@ macro_rules! cli_send {
 ///
 /// cli_sendln!("Hello, world!");
 /// cli_sendln!("User: {}", "Alice");
+///
+/// let (name, total) = ("Alice", 42.5);
+/// cli_sendln!("{name:<10} {total:>8.2} {{literal}}");
+///
+/// let message = String::from("Shown as is: {}");
+/// cli_sendln!(message);
 /// ```
 #[macro_export]
 macro_rules! cli_sendln {
-    ($text:expr) => { $crate::cli_log($crate::CliLevel::SendLn, $text, &[]) };
-    ($text:expr, $( $arg:expr ),*) => {{
-        let mut args = vec![];
-        $( args.push($arg.to_string()); )*
-        $crate::cli_log($crate::CliLevel::SendLn, $text, &args)
-    }};
+    () => { $crate::cli_log($crate::CliLevel::SendLn, format_args!("")) };
+    ($fmt:literal $($arg:tt)*) => {
+        $crate::cli_log($crate::CliLevel::SendLn, format_args!($fmt $($arg)*))
+    };
+    ($text:expr) => {
+        $crate::cli_log($crate::CliLevel::SendLn, format_args!("{}", $text))
+    };
 }
 
 /// Outputs an informational message.
 ///
 /// Displays text and optionally logs to the configured logger when the `log` feature is enabled.
+/// Hidden with `CliVerbosity::Quiet`.
 ///
 /// # Example
 ///
@@ -152,12 +244,12 @@ macro_rules! cli_sendln {
 /// ```
 #[macro_export]
 macro_rules! cli_info {
-    ($text:expr) => { $crate::cli_log($crate::CliLevel::Info, $text, &[]) };
-    ($text:expr, $( $arg:expr ),*) => {{
-        let mut args = vec![];
-        $( args.push($arg.to_string()); )*
-        $crate::cli_log($crate::CliLevel::Info, $text, &args)
-    }};
+    ($fmt:literal $($arg:tt)*) => {
+        $crate::cli_log($crate::CliLevel::Info, format_args!($fmt $($arg)*))
+    };
+    ($text:expr) => {
+        $crate::cli_log($crate::CliLevel::Info, format_args!("{}", $text))
+    };
 }
 
 /// Outputs a warning message.
@@ -174,12 +266,12 @@ macro_rules! cli_info {
 /// ```
 #[macro_export]
 macro_rules! cli_warn {
-    ($text:expr) => { $crate::cli_log($crate::CliLevel::Warn, $text, &[]) };
-    ($text:expr, $( $arg:expr ),*) => {{
-        let mut args = vec![];
-        $( args.push($arg.to_string()); )*
-        $crate::cli_log($crate::CliLevel::Warn, $text, &args)
-    }};
+    ($fmt:literal $($arg:tt)*) => {
+        $crate::cli_log($crate::CliLevel::Warn, format_args!($fmt $($arg)*))
+    };
+    ($text:expr) => {
+        $crate::cli_log($crate::CliLevel::Warn, format_args!("{}", $text))
+    };
 }
 
 /// Outputs an error message.
@@ -192,21 +284,23 @@ macro_rules! cli_warn {
 /// use falcon_cli::cli_error;
 ///
 /// cli_error!("Failed to connect to database");
+/// # let input_value = "abc";
 /// cli_error!("Invalid input: {}", input_value);
 /// ```
 #[macro_export]
 macro_rules! cli_error {
-    ($text:expr) => { $crate::cli_log($crate::CliLevel::Error, $text, &[]) };
-    ($text:expr, $( $arg:expr ),*) => {{
-        let mut args = vec![];
-        $( args.push($arg.to_string()); )*
-        $crate::cli_log($crate::CliLevel::Error, $text, &args)
-    }};
+    ($fmt:literal $($arg:tt)*) => {
+        $crate::cli_log($crate::CliLevel::Error, format_args!($fmt $($arg)*))
+    };
+    ($text:expr) => {
+        $crate::cli_log($crate::CliLevel::Error, format_args!("{}", $text))
+    };
 }
 
 /// Outputs a debug message.
 ///
 /// Displays text and logs to the configured logger when the `log` feature is enabled.
+/// Only displayed with `CliVerbosity::Verbose` or higher.
 ///
 /// # Example
 ///
@@ -214,22 +308,23 @@ macro_rules! cli_error {
 /// use falcon_cli::cli_debug;
 ///
 /// cli_debug!("Processing step 1 of 3");
+/// # let debug_value = 42;
 /// cli_debug!("Variable value: {}", debug_value);
 /// ```
 #[macro_export]
 macro_rules! cli_debug {
-    ($text:expr) => { $crate::cli_log($crate::CliLevel::Debug, $text, &[]) };
-    ($text:expr, $( $arg:expr ),*) => {{
-        let mut args = vec![];
-        $( args.push($arg.to_string()); )*
-        $crate::cli_log($crate::CliLevel::Debug, $text, &args)
-    }};
+    ($fmt:literal $($arg:tt)*) => {
+        $crate::cli_log($crate::CliLevel::Debug, format_args!($fmt $($arg)*))
+    };
+    ($text:expr) => {
+        $crate::cli_log($crate::CliLevel::Debug, format_args!("{}", $text))
+    };
 }
 
 /// Outputs a trace message.
 ///
 /// Displays text and logs to the configured logger when the `log` feature is enabled.
-/// Used for very detailed diagnostic information.
+/// Used for very detailed diagnostic information, only displayed with `CliVerbosity::Trace`.
 ///
 /// # Example
 ///
@@ -237,14 +332,15 @@ macro_rules! cli_debug {
 /// use falcon_cli::cli_trace;
 ///
 /// cli_trace!("Entering function parse_config");
+/// # let i = 1;
 /// cli_trace!("Loop iteration: {}", i);
 /// ```
 #[macro_export]
 macro_rules! cli_trace {
-    ($text:expr) => { $crate::cli_log($crate::CliLevel::Trace, $text, &[]) };
-    ($text:expr, $( $arg:expr ),*) => {{
-        let mut args = vec![];
-        $( args.push($arg.to_string()); )*
-        $crate::cli_log($crate::CliLevel::Trace, $text, &args)
-    }};
+    ($fmt:literal $($arg:tt)*) => {
+        $crate::cli_log($crate::CliLevel::Trace, format_args!($fmt $($arg)*))
+    };
+    ($text:expr) => {
+        $crate::cli_log($crate::CliLevel::Trace, format_args!("{}", $text))
+    };
 }
@@ -0,0 +1,246 @@
+// Copyright 2025 Aquila Labs of Alberta, Canada <matt@cicero.sh>
+// Licensed under either the Apache License, Version 2.0 OR the MIT License, at your option.
+// You may not use this file except in compliance with one of the Licenses.
+// Apache License text: https://www.apache.org/licenses/LICENSE-2.0
+// MIT License text: https://opensource.org/licenses/MIT
+
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// The output streams all CLI output is written to.
+///
+/// Normal output goes to `Stdout`, while errors and warnings go to `Stderr`.  Either may be
+/// redirected via `cli_set_output()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CliStream {
+    /// Normal output, written to STDOUT by default.
+    Stdout,
+    /// Errors and warnings, written to STDERR by default.
+    Stderr,
+}
+
+#[cfg(feature = "mock")]
+thread_local! {
+    static CAPTURE: std::cell::RefCell<Option<(CliBuffer, CliBuffer)>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+#[cfg(feature = "mock")]
+thread_local! {
+    static TEST_WIDTH: std::cell::Cell<Option<usize>> = const { std::cell::Cell::new(None) };
+}
+
+static WIDTH: AtomicUsize = AtomicUsize::new(0);
+static STDOUT_SINK: Mutex<Option<Box<dyn Write + Send>>> = Mutex::new(None);
+static STDERR_SINK: Mutex<Option<Box<dyn Write + Send>>> = Mutex::new(None);
+
+/// Redirects one of the output streams to the given writer.
+///
+/// Affects all output from this crate, including the `cli_*` macros, help screens, tables,
+/// headers and progress bars, across all threads.  The writer may be anything implementing
+/// `io::Write`, such as `io::stderr()`, a file, a `CliBuffer` or a TUI widget.
+///
+/// # Arguments
+///
+/// * `stream` - The stream to redirect
+/// * `writer` - Where output for the stream will be written
+///
+/// # Example
+///
+/// ```
+/// use falcon_cli::{CliBuffer, CliStream, cli_header, cli_reset_output, cli_set_output};
+///
+/// let buffer = CliBuffer::new();
+/// cli_set_output(CliStream::Stdout, buffer.clone());
+/// cli_header("Report");
+/// cli_reset_output();
+///
+/// assert!(buffer.contents().contains("-- Report\n"));
+/// ```
+pub fn cli_set_output<W: Write + Send + 'static>(stream: CliStream, writer: W) {
+    *sink(stream).lock().unwrap_or_else(|e| e.into_inner()) = Some(Box::new(writer));
+}
+
+/// Resets both output streams back to STDOUT and STDERR.
+pub fn cli_reset_output() {
+    for stream in [CliStream::Stdout, CliStream::Stderr] {
+        *sink(stream).lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+/// Writes text to one of the output streams, and flushes it.
+///
+/// All output from this crate goes through this function, so it may also be used to keep
+/// the application's own output consistent with wherever output has been redirected.
+/// Errors while writing are ignored.
+///
+/// # Arguments
+///
+/// * `stream` - The stream to write to
+/// * `text` - The text to write, as is
+///
+/// # Example
+///
+/// ```
+/// use falcon_cli::{CliStream, cli_write};
+///
+/// cli_write(CliStream::Stderr, "Something went wrong\n");
+/// ```
+pub fn cli_write(stream: CliStream, text: &str) {
+    #[cfg(feature = "mock")]
+    if let Some(mut buffer) = CAPTURE.with_borrow(|capture| {
+        capture.as_ref().map(|(out, err)| match stream {
+            CliStream::Stdout => out.clone(),
+            CliStream::Stderr => err.clone(),
+        })
+    }) {
+        let _ = buffer.write_all(text.as_bytes());
+        return;
+    }
+
+    let mut sink = sink(stream).lock().unwrap_or_else(|e| e.into_inner());
+    let _ = match sink.as_mut() {
+        Some(writer) => writer.write_all(text.as_bytes()).and_then(|_| writer.flush()),
+        None if stream == CliStream::Stderr => {
+            let mut stderr = io::stderr().lock();
+            stderr.write_all(text.as_bytes()).and_then(|_| stderr.flush())
+        }
+        None => {
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(text.as_bytes()).and_then(|_| stdout.flush())
+        }
+    };
+}
+
+/// Captures all output written from the current thread into the given buffers, returning
+/// the previous capture.
+#[cfg(feature = "mock")]
+pub(crate) fn set_capture(
+    capture: Option<(CliBuffer, CliBuffer)>,
+) -> Option<(CliBuffer, CliBuffer)> {
+    CAPTURE.replace(capture)
+}
+
+/// Checks whether the stream is written to a terminal, and has not been redirected.
+pub(crate) fn is_terminal(stream: CliStream) -> bool {
+    #[cfg(feature = "mock")]
+    if CAPTURE.with_borrow(|capture| capture.is_some()) {
+        return false;
+    }
+
+    if sink(stream).lock().unwrap_or_else(|e| e.into_inner()).is_some() {
+        return false;
+    }
+
+    match stream {
+        CliStream::Stdout => io::stdout().is_terminal(),
+        CliStream::Stderr => io::stderr().is_terminal(),
+    }
+}
+
+fn sink(stream: CliStream) -> &'static Mutex<Option<Box<dyn Write + Send>>> {
+    match stream {
+        CliStream::Stdout => &STDOUT_SINK,
+        CliStream::Stderr => &STDERR_SINK,
+    }
+}
+
+/// Overrides the terminal width used to wrap text and size help screens, tables and
+/// progress bars.
+///
+/// # Arguments
+///
+/// * `width` - The width in columns, or 0 to detect it again
+///
+/// # Example
+///
+/// ```
+/// use falcon_cli::{cli_set_width, cli_width};
+///
+/// cli_set_width(120);
+/// assert_eq!(cli_width(), 120);
+/// ```
+pub fn cli_set_width(width: usize) {
+    WIDTH.store(width, Ordering::Relaxed);
+}
+
+/// Gets the terminal width, in columns.
+///
+/// Returns the width set via `cli_set_width()` if any, otherwise the `COLUMNS` environment
+/// variable if set, otherwise the width of the terminal, falling back to 80 when STDOUT is
+/// not a terminal.
+pub fn cli_width() -> usize {
+    match WIDTH.load(Ordering::Relaxed) {
+        0 => {}
+        width => return width,
+    }
+
+    #[cfg(feature = "mock")]
+    if let Some(width) = TEST_WIDTH.get() {
+        return width;
+    }
+
+    if let Ok(columns) = env::var("COLUMNS")
+        && let Ok(width) = columns.trim().parse::<usize>()
+        && width > 0
+    {
+        return width;
+    }
+
+    match terminal_size::terminal_size() {
+        Some((terminal_size::Width(width), _)) if width > 0 => width as usize,
+        _ => 80,
+    }
+}
+
+/// Gets the width text is wrapped at, leaving a small margin on the right.
+pub(crate) fn wrap_width() -> usize {
+    cli_width().saturating_sub(5).max(20)
+}
+
+/// Sets the width used on the current thread when no width was set via `cli_set_width()`,
+/// returning the previous one.
+#[cfg(feature = "mock")]
+pub(crate) fn set_test_width(width: Option<usize>) -> Option<usize> {
+    TEST_WIDTH.replace(width)
+}
+
+/// An in-memory writer that output can be redirected to, such as for tests.
+///
+/// Clones share the same buffer, so one clone can be passed to `cli_set_output()` while
+/// another is used to read what was written.
+#[derive(Clone, Default)]
+pub struct CliBuffer {
+    data: Arc<Mutex<Vec<u8>>>,
+}
+
+impl CliBuffer {
+    /// Creates a new, empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets everything written to the buffer so far.
+    pub fn contents(&self) -> String {
+        let data = self.data.lock().unwrap_or_else(|e| e.into_inner());
+        String::from_utf8_lossy(&data).to_string()
+    }
+
+    /// Clears the buffer.
+    pub fn clear(&self) {
+        self.data.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+impl Write for CliBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.data.lock().unwrap_or_else(|e| e.into_inner()).extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
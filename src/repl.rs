@@ -0,0 +1,322 @@
+// Copyright 2025 Aquila Labs of Alberta, Canada <matt@cicero.sh>
+// Licensed under either the Apache License, Version 2.0 OR the MIT License, at your option.
+// You may not use this file except in compliance with one of the Licenses.
+// Apache License text: https://www.apache.org/licenses/LICENSE-2.0
+// MIT License text: https://opensource.org/licenses/MIT
+
+use crate::error::CliError;
+use crate::request::CliValueSource;
+use crate::router::CliRouter;
+use crate::{cli_error_line, run_parsed};
+use std::env;
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Runs an interactive shell, executing each line entered as a command.
+///
+/// Lines are split into arguments with shell-like quoting via `cli_split_line()`, and routed
+/// through the same pipeline as `cli_run()`.  Global flags given when the application was
+/// started, or entered on a line by themselves, are kept for the whole session along with
+/// their source, `unset <flag>...` removes kept flags from the session, and the session ends
+/// with `exit`, `quit` or Ctrl+D.  Global flags set via environment variables or configuration
+/// files are read again for each line, rather than kept.
+///
+/// With the `repl` feature enabled, lines can be edited and the history is saved to the file
+/// set via `CliRouter::history_file()`.
+///
+/// # Arguments
+///
+/// * `router` - The CLI router containing all registered commands
+/// * `prompt` - The prompt displayed before each line
+///
+/// # Returns
+///
+/// Returns `ExitCode::SUCCESS` once the session ends, or a failure if the terminal could not
+/// be read.
+///
+/// # Example
+///
+/// ```no_run
+/// use falcon_cli::{CliRouter, cli_repl};
+/// use std::process::ExitCode;
+///
+/// fn main() -> ExitCode {
+///     let mut router = CliRouter::new();
+///     router.global("-e", "--env", true, "Environment to use");
+///     router.history_file("/home/user/.myapp_history");
+///     // router.add::<...>(...);
+///
+///     cli_repl(&mut router, "myapp> ")
+/// }
+/// ```
+pub fn cli_repl(router: &mut CliRouter, prompt: &str) -> ExitCode {
+    let mut reader = match LineReader::new(router.history_file.clone()) {
+        Ok(r) => r,
+        Err(e) => {
+            cli_error_line(&e);
+            return ExitCode::from(router.exit_codes.other);
+        }
+    };
+
+    // Get session global flags from command line
+    if !router.parsed_global_flags {
+        router.parse_args(env::args_os().skip(1));
+    }
+    keep_session_flags(router);
+
+    loop {
+        let line = match reader.read_line(prompt) {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(e) => {
+                cli_error_line(&e);
+                return ExitCode::from(router.exit_codes.other);
+            }
+        };
+
+        let args = match cli_split_line(&line) {
+            Ok(args) if args.is_empty() => continue,
+            Ok(args) => args,
+            Err(e) => {
+                cli_error_line(&e);
+                continue;
+            }
+        };
+
+        if ["exit", "quit"].contains(&args[0].as_str()) && !router.commands.contains_key(&args[0]) {
+            break;
+        }
+        if args[0] == "unset" && !router.commands.contains_key("unset") {
+            unset_session_flags(router, &args[1..]);
+            continue;
+        }
+        run_line(router, args);
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Splits a line into arguments, similar to how a shell would.
+///
+/// Arguments are separated by whitespace, which may be included within an argument by
+/// surrounding it with single or double quotes, or escaping it with a backslash.  Within
+/// double quotes, a backslash only escapes `"`, `\`, `$` and `` ` ``.
+///
+/// # Arguments
+///
+/// * `line` - The line to split
+///
+/// # Returns
+///
+/// Returns the list of arguments, or `CliError::Generic` if a quote was not closed.
+///
+/// # Example
+///
+/// ```
+/// use falcon_cli::cli_split_line;
+///
+/// let args = cli_split_line(r#"domain create "my site.com" --note='a \ b' c\ d"#).unwrap();
+/// assert_eq!(args, vec!["domain", "create", "my site.com", "--note=a \\ b", "c d"]);
+///
+/// let args = cli_split_line(r#"deploy --msg "say \"hi\"""#).unwrap();
+/// assert_eq!(args, vec!["deploy", "--msg", "say \"hi\""]);
+///
+/// assert!(cli_split_line("deploy 'unclosed").is_err());
+/// ```
+pub fn cli_split_line(line: &str) -> Result<Vec<String>, CliError> {
+    let unterminated = || CliError::Generic("Unterminated quote within line".to_string());
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err(unterminated()),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => current.push(c),
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => return Err(unterminated()),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err(unterminated()),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                if let Some(c) = chars.next() {
+                    current.push(c);
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    args.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            c => {
+                in_word = true;
+                current.push(c);
+            }
+        }
+    }
+
+    if in_word {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+/// Executes a single line within the shell.
+fn run_line(router: &mut CliRouter, args: Vec<String>) {
+    // Global flags entered by themselves are kept for the session
+    match router.parse_args(args) {
+        Some(args) => {
+            run_parsed(router, Some(args));
+        }
+        None => keep_session_flags(router),
+    }
+}
+
+/// Keeps the global flags given on the command line, or kept from a previous line, for the
+/// rest of the session.
+fn keep_session_flags(router: &mut CliRouter) {
+    router.session_flags = router
+        .global_flags
+        .iter()
+        .filter(|gf| gf.has && gf.source == Some(CliValueSource::CommandLine))
+        .cloned()
+        .collect();
+}
+
+/// Removes global flags from the session, accepting either their short or long form.
+fn unset_session_flags(router: &mut CliRouter, flags: &[String]) {
+    for flag in flags {
+        let before = router.session_flags.len();
+        router.session_flags.retain(|gf| gf.short != *flag && gf.long != *flag);
+        if router.session_flags.len() == before {
+            cli_error_line(&format!("Global flag {} is not set for this session", flag));
+        }
+    }
+}
+
+/// Reads lines from the terminal, with line editing and history.
+#[cfg(feature = "repl")]
+struct LineReader {
+    editor: rustyline::DefaultEditor,
+    history_file: Option<PathBuf>,
+}
+
+#[cfg(feature = "repl")]
+impl LineReader {
+    fn new(history_file: Option<PathBuf>) -> io::Result<Self> {
+        let mut editor = rustyline::DefaultEditor::new().map_err(io::Error::other)?;
+        if let Some(path) = &history_file {
+            let _ = editor.load_history(path);
+        }
+        Ok(Self {
+            editor,
+            history_file,
+        })
+    }
+
+    /// Reads the next line, returning `None` once the input has ended.
+    fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        use rustyline::error::ReadlineError;
+
+        match self.editor.readline(prompt) {
+            Ok(line) => {
+                if !line.trim().is_empty() {
+                    let _ = self.editor.add_history_entry(line.as_str());
+                    if let Some(path) = &self.history_file {
+                        let _ = self.editor.save_history(path);
+                    }
+                }
+                Ok(Some(line))
+            }
+            Err(ReadlineError::Interrupted) => Ok(Some(String::new())),
+            Err(ReadlineError::Eof) => Ok(None),
+            Err(e) => Err(io::Error::other(e)),
+        }
+    }
+}
+
+/// Reads lines from STDIN.
+#[cfg(not(feature = "repl"))]
+struct LineReader;
+
+#[cfg(not(feature = "repl"))]
+impl LineReader {
+    fn new(_history_file: Option<PathBuf>) -> io::Result<Self> {
+        Ok(Self)
+    }
+
+    /// Reads the next line, returning `None` once the input has ended.
+    fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        use crate::output::{CliStream, cli_write};
+
+        cli_write(CliStream::Stdout, prompt);
+
+        let mut line = String::new();
+        match io::stdin().read_line(&mut line)? {
+            0 => Ok(None),
+            _ => Ok(Some(line)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_router() -> CliRouter {
+        let mut router = CliRouter::new();
+        router.global("-e", "--env", true, "Environment");
+        router.global("-d", "--dry-run", false, "Dry run");
+        router.global("-r", "--region", true, "Region").env("FALCON_REPL_TEST_REGION");
+        router
+    }
+
+    #[test]
+    fn session_flags_keep_their_source() {
+        let mut router = build_router();
+        unsafe { env::set_var("FALCON_REPL_TEST_REGION", "us-east") };
+        router.parse_args(["--env", "prod", "-d"]);
+        keep_session_flags(&mut router);
+        unsafe { env::remove_var("FALCON_REPL_TEST_REGION") };
+
+        assert_eq!(router.session_flags.len(), 2);
+        router.parse_args(["deploy"]);
+        assert_eq!(router.get_global("--env"), Some("prod".to_string()));
+        assert_eq!(
+            router.global_source("--env"),
+            Some(CliValueSource::CommandLine)
+        );
+        assert!(router.has_global("--dry-run"));
+        assert!(!router.has_global("--region"));
+
+        unset_session_flags(&mut router, &["-d".to_string()]);
+        router.parse_args(["deploy"]);
+        assert!(!router.has_global("--dry-run"));
+        assert!(router.has_global("--env"));
+    }
+}
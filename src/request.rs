@@ -5,15 +5,19 @@
 // MIT License text: https://opensource.org/licenses/MIT
 
 use crate::error::CliError;
+use std::any::type_name;
 use std::collections::HashMap;
-use std::fs;
 use std::ops::Range;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::{fmt, fs};
 use url::Url;
 
 /// Represents a parsed CLI command request.
 ///
 /// This struct contains all the parsed information from a command line invocation,
 /// including the command name, arguments, flags, and their values.
+#[derive(Default)]
 pub struct CliRequest {
     /// The primary alias of the command that was invoked.
     pub cmd_alias: String,
@@ -23,19 +27,54 @@ pub struct CliRequest {
     pub args: Vec<String>,
     /// Boolean flags that were provided (e.g., `-v`, `--verbose`).
     pub flags: Vec<String>,
-    /// Flags with associated values (e.g., `--output file.txt`).
+    /// Flags with associated values (e.g., `--output file.txt`).  For repeatable flags
+    /// this holds the last value given.
     pub flag_values: HashMap<String, String>,
+    /// All values of repeatable flags, in the order given (e.g., `--tag a --tag b`).
+    pub flag_lists: HashMap<String, Vec<String>>,
+    /// Arguments given after the `--` separator, passed through verbatim without flag parsing.
+    pub trailing_args: Vec<String>,
     /// List of shortcut aliases for this command.
     pub shortcuts: Vec<String>,
+    /// When completing, the value flag whose value is being completed (e.g., `--output`).
+    /// If `None`, the next positional parameter at `args.len()` is being completed.
+    pub completing_flag: Option<String>,
+    /// Where the values of value flags declared within the command's specification came from.
+    pub flag_sources: HashMap<String, CliValueSource>,
+}
+
+/// Where the value of a flag came from, from highest to lowest precedence.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliValueSource {
+    /// Given on the command line.
+    CommandLine,
+    /// Read from the contained environment variable.
+    Env(String),
+    /// Loaded from the contained configuration file.
+    Config(PathBuf),
+    /// The default declared within the command's specification.
+    Default,
+}
+
+impl fmt::Display for CliValueSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliValueSource::CommandLine => write!(f, "command line"),
+            CliValueSource::Env(name) => write!(f, "environment variable {}", name),
+            CliValueSource::Config(path) => write!(f, "config file {}", path.display()),
+            CliValueSource::Default => write!(f, "default"),
+        }
+    }
 }
 
 /// Format validators for command arguments and flags.
 ///
 /// These validators can be used to ensure arguments and flags conform to
 /// expected formats before processing.
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Default)]
 pub enum CliFormat {
     /// Accept any string value.
+    #[default]
     Any,
     /// Must be a valid integer.
     Integer,
@@ -121,7 +160,7 @@ impl CliRequest {
     /// # }
     /// ```
     pub fn require_flag(&self, flag: &str) -> Result<(), CliError> {
-        if self.has_flag(&flag) {
+        if self.has_flag(flag) {
             Ok(())
         } else {
             Err(CliError::MissingFlag(flag.to_string()))
@@ -149,12 +188,111 @@ impl CliRequest {
     /// # }
     /// ```
     pub fn get_flag(&self, flag: &str) -> Option<String> {
-        match self.flag_values.get(&flag.to_string()) {
-            Some(r) => Some(r.clone()),
-            None => None,
+        self.flag_values.get(flag).cloned()
+    }
+
+    /// Gets where the value of a flag came from.
+    ///
+    /// Value flags declared within the command's specification may be given on the command
+    /// line, or fall back to an environment variable, configuration file or default.  Any
+    /// other flag that was provided came from the command line.
+    ///
+    /// # Arguments
+    ///
+    /// * `flag` - The name of the flag
+    ///
+    /// # Returns
+    ///
+    /// Returns the source of the flag's value, or `None` if the flag has no value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliCommandSpec, CliFormat, CliRequest, CliValueSource};
+    /// let mut spec = CliCommandSpec::new();
+    /// spec.add_value_flag("-r", "--region", "Region to deploy to", CliFormat::Any).default("us-east");
+    ///
+    /// let mut req = CliRequest::default();
+    /// spec.apply(&mut req).unwrap();
+    /// assert_eq!(req.get_flag_source("-r"), Some(CliValueSource::Default));
+    /// ```
+    pub fn get_flag_source(&self, flag: &str) -> Option<CliValueSource> {
+        match self.flag_sources.get(flag) {
+            Some(source) => Some(source.clone()),
+            None => self.has_flag(flag).then_some(CliValueSource::CommandLine),
         }
     }
 
+    /// Gets all values of a flag, in the order they were provided.
+    ///
+    /// Repeatable flags (registered with a `...` suffix) return every value given, while
+    /// other value flags return their single value.
+    ///
+    /// # Arguments
+    ///
+    /// * `flag` - The name of the flag
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of the flag's values, which is empty if the flag wasn't provided.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliRouter, CliCommand, CliRequest, CliHelpScreen};
+    /// # #[derive(Default)]
+    /// # struct BuildCommand;
+    /// # impl CliCommand for BuildCommand {
+    /// #   fn process(&self, req: &CliRequest) -> anyhow::Result<()> { Ok(()) }
+    /// #   fn help(&self) -> CliHelpScreen { CliHelpScreen::new("", "", "") }
+    /// # }
+    /// let mut router = CliRouter::new();
+    /// router.add::<BuildCommand>("build", vec![], vec!["--tag..."]);
+    ///
+    /// let (req, _) = router.lookup_from(["build", "--tag", "a", "--tag=b"]).command().unwrap();
+    /// assert_eq!(req.get_flag_all("--tag"), vec!["a", "b"]);
+    /// assert_eq!(req.get_flag("--tag"), Some("b".to_string()));
+    /// ```
+    pub fn get_flag_all(&self, flag: &str) -> Vec<String> {
+        match self.flag_lists.get(flag) {
+            Some(values) => values.clone(),
+            None => self.get_flag(flag).into_iter().collect(),
+        }
+    }
+
+    /// Gets the number of times a boolean flag was provided.
+    ///
+    /// Combined short flags are counted individually, so `-vvv` counts as three `-v` flags.
+    ///
+    /// # Arguments
+    ///
+    /// * `flag` - The name of the flag
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of times the flag was provided, or 0 if it wasn't.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliRouter, CliCommand, CliRequest, CliHelpScreen};
+    /// # #[derive(Default)]
+    /// # struct BuildCommand;
+    /// # impl CliCommand for BuildCommand {
+    /// #   fn process(&self, req: &CliRequest) -> anyhow::Result<()> { Ok(()) }
+    /// #   fn help(&self) -> CliHelpScreen { CliHelpScreen::new("", "", "") }
+    /// # }
+    /// let mut router = CliRouter::new();
+    /// router.add::<BuildCommand>("build", vec![], vec![]);
+    ///
+    /// let (req, _) = router.lookup_from(["build", "-vvv"]).command().unwrap();
+    /// assert_eq!(req.get_flag_count("-v"), 3);
+    /// assert_eq!(req.get_flag_count("-q"), 0);
+    /// ```
+    pub fn get_flag_count(&self, flag: &str) -> usize {
+        self.flags.iter().filter(|f| *f == flag).count()
+    }
+
     /// Validates that a flag's value conforms to the specified format.
     ///
     /// # Arguments
@@ -181,11 +319,150 @@ impl CliRequest {
     /// # }
     /// ```
     pub fn validate_flag(&self, flag: &str, format: CliFormat) -> Result<(), CliError> {
-        let value = self.get_flag(&flag).ok_or(CliError::MissingFlag(flag.to_string()))?;
-        self.validate(0, &value, format.clone())?;
+        let value = self.get_flag(flag).ok_or(CliError::MissingFlag(flag.to_string()))?;
+        self.validate(0, &value, format.clone()).map_err(|e| match e {
+            CliError::InvalidParam(_, msg) => CliError::InvalidFlag(flag.to_string(), msg),
+            other => other,
+        })?;
         Ok(())
     }
 
+    /// Gets the value of a flag parsed into the desired type.
+    ///
+    /// # Arguments
+    ///
+    /// * `flag` - The name of the flag
+    ///
+    /// # Returns
+    ///
+    /// Returns the parsed value, `CliError::MissingFlag` if the flag wasn't provided, or
+    /// `CliError::InvalidFlag` if its value could not be parsed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliRouter, CliCommand, CliRequest, CliHelpScreen};
+    /// # #[derive(Default)]
+    /// # struct ServeCommand;
+    /// # impl CliCommand for ServeCommand {
+    /// #   fn process(&self, req: &CliRequest) -> anyhow::Result<()> { Ok(()) }
+    /// #   fn help(&self) -> CliHelpScreen { CliHelpScreen::new("", "", "") }
+    /// # }
+    /// let mut router = CliRouter::new();
+    /// router.add::<ServeCommand>("serve", vec![], vec!["--port", "--host"]);
+    ///
+    /// let (req, _) = router.lookup_from(["serve", "--port", "8080", "--host", "abc"]).command().unwrap();
+    /// assert_eq!(req.get_flag_as::<u16>("--port").unwrap(), 8080);
+    /// assert_eq!(
+    ///     req.get_flag_as::<u16>("--host").unwrap_err().to_string(),
+    ///     "Invalid value for flag --host: Expected integer, got 'abc'"
+    /// );
+    /// ```
+    pub fn get_flag_as<T: FromStr>(&self, flag: &str) -> Result<T, CliError> {
+        let value = self.get_flag(flag).ok_or(CliError::MissingFlag(flag.to_string()))?;
+        parse_value(&value).map_err(|msg| CliError::InvalidFlag(flag.to_string(), msg))
+    }
+
+    /// Gets the value of a flag parsed into the desired type, or a default if not provided.
+    ///
+    /// # Arguments
+    ///
+    /// * `flag` - The name of the flag
+    /// * `default` - The value to return if the flag wasn't provided
+    ///
+    /// # Returns
+    ///
+    /// Returns the parsed value or default, or `CliError::InvalidFlag` if the flag was
+    /// provided but its value could not be parsed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use falcon_cli::{CliRequest, CliCommand, CliHelpScreen};
+    /// # struct MyCmd;
+    /// # impl CliCommand for MyCmd {
+    /// #   fn help(&self) -> CliHelpScreen { CliHelpScreen::new("", "", "") }
+    /// fn process(&self, req: &CliRequest) -> anyhow::Result<()> {
+    ///     let port = req.get_flag_or::<u16>("--port", 80)?;
+    ///     let workers = req.get_flag_or("--workers", 4_usize)?;
+    ///     Ok(())
+    /// }
+    /// # }
+    /// ```
+    pub fn get_flag_or<T: FromStr>(&self, flag: &str, default: T) -> Result<T, CliError> {
+        match self.get_flag(flag) {
+            Some(value) => {
+                parse_value(&value).map_err(|msg| CliError::InvalidFlag(flag.to_string(), msg))
+            }
+            None => Ok(default),
+        }
+    }
+
+    /// Gets all values of a flag parsed into the desired type, in the order they were provided.
+    ///
+    /// # Arguments
+    ///
+    /// * `flag` - The name of the flag
+    ///
+    /// # Returns
+    ///
+    /// Returns the parsed values, which is empty if the flag wasn't provided, or
+    /// `CliError::InvalidFlag` if any value could not be parsed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use falcon_cli::{CliRequest, CliCommand, CliHelpScreen};
+    /// # struct MyCmd;
+    /// # impl CliCommand for MyCmd {
+    /// #   fn help(&self) -> CliHelpScreen { CliHelpScreen::new("", "", "") }
+    /// fn process(&self, req: &CliRequest) -> anyhow::Result<()> {
+    ///     let ports = req.get_flag_all_as::<u16>("--port")?;
+    ///     Ok(())
+    /// }
+    /// # }
+    /// ```
+    pub fn get_flag_all_as<T: FromStr>(&self, flag: &str) -> Result<Vec<T>, CliError> {
+        self.get_flag_all(flag)
+            .iter()
+            .map(|value| {
+                parse_value(value).map_err(|msg| CliError::InvalidFlag(flag.to_string(), msg))
+            })
+            .collect()
+    }
+
+    /// Gets the parameter at the specified position parsed into the desired type.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - The position of the parameter (0-indexed)
+    ///
+    /// # Returns
+    ///
+    /// Returns the parsed value, or `CliError::InvalidParam` if the parameter wasn't
+    /// provided or could not be parsed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use falcon_cli::{CliRequest, CliCommand, CliHelpScreen};
+    /// # struct MyCmd;
+    /// # impl CliCommand for MyCmd {
+    /// #   fn help(&self) -> CliHelpScreen { CliHelpScreen::new("", "", "") }
+    /// fn process(&self, req: &CliRequest) -> anyhow::Result<()> {
+    ///     let user_id = req.get_param_as::<u64>(0)?;
+    ///     let amount = req.get_param_as::<f64>(1)?;
+    ///     Ok(())
+    /// }
+    /// # }
+    /// ```
+    pub fn get_param_as<T: FromStr>(&self, pos: usize) -> Result<T, CliError> {
+        let arg = self.args.get(pos).ok_or_else(|| {
+            CliError::InvalidParam(pos, format!("Expected parameter at position {}", pos))
+        })?;
+        parse_value(arg).map_err(|msg| CliError::InvalidParam(pos, msg))
+    }
+
     /// Checks if a flag was provided.
     ///
     /// # Arguments
@@ -207,7 +484,7 @@ impl CliRequest {
     /// # }
     /// ```
     pub fn has_flag(&self, flag: &str) -> bool {
-        self.flags.contains(&flag.to_string()) || self.flag_values.contains_key(&flag.to_string())
+        self.flags.contains(&flag.to_string()) || self.flag_values.contains_key(flag)
     }
 
     /// Validates that all parameters conform to the specified formats.
@@ -243,7 +520,7 @@ impl CliRequest {
                 CliError::InvalidParam(pos, format!("Expected parameter at position {}", pos))
             })?;
 
-            self.validate(pos, &arg, format.clone())?;
+            self.validate(pos, arg, format.clone())?;
         }
 
         Ok(())
@@ -251,14 +528,19 @@ impl CliRequest {
 
     /// Validates a single value against a format specification.
     ///
-    /// Internal method used by `validate_params` and `validate_flag`.
+    /// Internal method used by `validate_params`, `validate_flag` and `CliCommandSpec`.
     ///
     /// # Arguments
     ///
     /// * `pos` - The position of the parameter (for error messages)
     /// * `arg` - The value to validate
     /// * `format` - The format validator to apply
-    fn validate(&self, pos: usize, arg: &str, format: CliFormat) -> Result<(), CliError> {
+    pub(crate) fn validate(
+        &self,
+        pos: usize,
+        arg: &str,
+        format: CliFormat,
+    ) -> Result<(), CliError> {
         match format {
             CliFormat::Any => return Ok(()),
             CliFormat::Integer => {
@@ -337,16 +619,12 @@ impl CliRequest {
                 if !options.contains(&arg.to_string()) {
                     return Err(CliError::InvalidParam(
                         pos,
-                        format!(
-                            "Expected one of ({}), got '{}'",
-                            options.join(" / ").to_string(),
-                            arg
-                        ),
+                        format!("Expected one of ({}), got '{}'", options.join(" / "), arg),
                     ));
                 }
             }
             CliFormat::File => {
-                let metadata = fs::metadata(&arg)?;
+                let metadata = fs::metadata(arg)?;
                 if !metadata.is_file() {
                     return Err(CliError::InvalidParam(
                         pos,
@@ -355,7 +633,7 @@ impl CliRequest {
                 }
             }
             CliFormat::Directory => {
-                let metadata = fs::metadata(&arg)?;
+                let metadata = fs::metadata(arg)?;
                 if !metadata.is_dir() {
                     return Err(CliError::InvalidParam(
                         pos,
@@ -368,3 +646,23 @@ impl CliRequest {
         Ok(())
     }
 }
+
+/// Parses a value into the desired type, returning an error message naming the expected type.
+fn parse_value<T: FromStr>(value: &str) -> Result<T, String> {
+    value.parse::<T>().map_err(|_| format!("Expected {}, got '{}'", type_label::<T>(), value))
+}
+
+/// Gets a user friendly name of a type for error messages.
+fn type_label<T>() -> String {
+    let name = type_name::<T>();
+    match name {
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64" | "u128"
+        | "usize" => "integer".to_string(),
+        "f32" | "f64" => "decimal number".to_string(),
+        "bool" => "boolean (true/false)".to_string(),
+        _ => {
+            let base = name.split('<').next().unwrap_or(name);
+            base.rsplit("::").next().unwrap_or(base).to_string()
+        }
+    }
+}
@@ -4,10 +4,12 @@
 // Apache License text: https://www.apache.org/licenses/LICENSE-2.0
 // MIT License text: https://opensource.org/licenses/MIT
 
-use super::{CliCommand, CliHelpScreen, CliRequest};
+use super::{CliCommand, CliRequest};
 use crate::*;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::env;
+use std::ffi::OsString;
+use std::path::PathBuf;
 use strsim::levenshtein;
 
 /// The main router for CLI commands.
@@ -37,6 +39,109 @@ pub struct CliRouter {
     pub parsed_global_flags: bool,
     /// Internal: Child routers for nested command structures.
     pub children: HashMap<String, Box<CliRouter>>,
+    /// Exit codes returned by `cli_run()` when a command fails.
+    pub exit_codes: CliExitCodes,
+    /// Binary name used within generated completion scripts.
+    pub bin_name: String,
+    /// Internal: Whether the built-in `completions` command is registered.
+    pub completions_enabled: bool,
+    /// File in which `cli_repl()` saves the line history between sessions.
+    pub history_file: Option<PathBuf>,
+    /// Internal: Whether the built-in `--color` global flag is registered.
+    pub color_flag: bool,
+    /// Internal: Whether the built-in `--quiet`, `-v` and `-vv` global flags are registered.
+    pub verbosity_flags: bool,
+    /// Internal: Whether the built-in `--log-file` global flag is registered.
+    pub log_file_flag: bool,
+    /// How mistyped commands are handled.
+    pub typo_mode: CliTypoMode,
+    /// Whether unique prefixes of command segments are matched.
+    pub prefix_matching: bool,
+    /// Prefix used to derive the environment variables of flags without one (e.g., "MYAPP").
+    pub env_prefix: String,
+    /// Name of the application used to locate its configuration files, set via
+    /// `enable_config()`.  Configuration files are not loaded while `None`.
+    #[cfg(feature = "config")]
+    pub config_name: Option<String>,
+    /// Internal: Global flags kept for the session by `cli_repl()`, used when not given.
+    pub session_flags: Vec<CliGlobalFlag>,
+    /// Values loaded from configuration files when global flags were last parsed.
+    #[cfg(feature = "config")]
+    pub config: CliConfig,
+}
+
+/// How the router handles arguments that do not match a command, but are similar to one.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CliTypoMode {
+    /// Prompt the user to confirm running the most similar command.  Falls back to
+    /// `Suggest(3)` when STDIN is not a terminal, such as within scripts and CI jobs.
+    #[default]
+    Prompt,
+    /// Display up to the given number of similar commands, and exit with the
+    /// `CliExitCodes::not_found` code.
+    Suggest(usize),
+    /// Run the most similar command, after displaying a warning.
+    AutoRun,
+}
+
+/// The outcome of routing arguments via `CliRouter::lookup()`.
+#[allow(clippy::large_enum_variant)]
+pub enum CliRoute<'a> {
+    /// Help was requested for the index (`None`) or a category (`Some(alias)`).  Also
+    /// returned when no arguments were given.
+    Help(Option<String>),
+    /// The version was requested via `-v` or `--version`, containing the version message.
+    Version(String),
+    /// A command was found, along with its parsed request.  Whether help was requested
+    /// for the command is set within `CliRequest::is_help`.
+    Command(CliRequest, &'a dyn CliCommand),
+    /// Similar commands to suggest, as no command matched the arguments.  Only returned with
+    /// `CliTypoMode::Suggest`, or `CliTypoMode::Prompt` when STDIN is not a terminal.
+    Suggestions(Vec<String>),
+    /// The commands matching an ambiguous prefix, when prefix matching is enabled via
+    /// `CliRouter::enable_prefix_matching()`.
+    Ambiguous(Vec<String>),
+    /// No command matched the arguments, and there were no similar commands or the suggested
+    /// one was declined.
+    NotFound,
+    /// The arguments could not be routed, such as when a configuration file could not be
+    /// loaded.
+    Error(CliError),
+}
+
+/// Why arguments could not be routed to a handler.
+enum LookupFailure {
+    /// Similar commands to suggest, if any.
+    Suggestions(Vec<String>),
+    /// The commands matching an ambiguous prefix.
+    Ambiguous(Vec<String>),
+}
+
+impl<'a> CliRoute<'a> {
+    /// Gets the request and command, if a command was found.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliRouter, CliCommand, CliRequest, CliHelpScreen};
+    /// # #[derive(Default)]
+    /// # struct BuildCommand;
+    /// # impl CliCommand for BuildCommand {
+    /// #   fn process(&self, req: &CliRequest) -> anyhow::Result<()> { Ok(()) }
+    /// #   fn help(&self) -> CliHelpScreen { CliHelpScreen::new("", "", "") }
+    /// # }
+    /// let mut router = CliRouter::new();
+    /// router.add::<BuildCommand>("build", vec![], vec![]);
+    ///
+    /// let (req, _) = router.lookup_from(["build", "app"]).command().unwrap();
+    /// assert_eq!(req.args, vec!["app"]);
+    /// ```
+    pub fn command(self) -> Option<(CliRequest, &'a dyn CliCommand)> {
+        match self {
+            CliRoute::Command(req, cmd) => Some((req, cmd)),
+            _ => None,
+        }
+    }
 }
 
 /// Handler configuration for a CLI command.
@@ -50,6 +155,10 @@ pub struct CliHandler {
     pub shortcuts: Vec<String>,
     /// Flags that expect a value (e.g., `--output filename`).
     pub value_flags: Vec<String>,
+    /// Value flags that may be given multiple times (e.g., `--tag a --tag b`).
+    pub repeatable_flags: Vec<String>,
+    /// Map of value flags to all names of the same flag (e.g., `-o` to `--output` and `-o`).
+    pub flag_names: HashMap<String, Vec<String>>,
 }
 
 /// A category for organizing related commands.
@@ -83,6 +192,55 @@ pub struct CliGlobalFlag {
     pub has: bool,
     /// The value provided with this flag (if applicable).
     pub value: Option<String>,
+    /// Environment variable used when the flag is not provided (e.g., "MYAPP_CONFIG").
+    pub env: Option<String>,
+    /// Where the flag was set from, if it was.
+    pub source: Option<CliValueSource>,
+}
+
+impl CliGlobalFlag {
+    /// Sets the environment variable used when the flag is not provided.
+    ///
+    /// Value flags take the variable's value, and boolean flags are set when the variable
+    /// is `1`, `true` or `yes`.  Empty variables are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the environment variable (e.g., "MYAPP_CONFIG")
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::CliRouter;
+    /// let mut router = CliRouter::new();
+    /// router.global("-c", "--config", true, "Config file").env("DOCTEST_GLOBAL_CONFIG");
+    ///
+    /// // With DOCTEST_GLOBAL_CONFIG=prod.toml set in the environment
+    /// # unsafe { std::env::set_var("DOCTEST_GLOBAL_CONFIG", "prod.toml") };
+    /// router.lookup_from(["help"]);
+    /// assert_eq!(router.get_global("--config"), Some("prod.toml".to_string()));
+    ///
+    /// router.lookup_from(["--config", "dev.toml", "help"]);
+    /// assert_eq!(router.get_global("--config"), Some("dev.toml".to_string()));
+    /// ```
+    pub fn env(&mut self, name: &str) -> &mut Self {
+        self.env = Some(name.to_string());
+        self
+    }
+
+    /// Sets the flag from an environment variable or configuration file, as it was not given
+    /// on the command line.  Boolean flags are only set by `1`, `true` or `yes`.
+    fn set_fallback(&mut self, value: String, source: CliValueSource) {
+        if value.is_empty() {
+            return;
+        } else if self.is_value {
+            self.value = Some(value);
+        } else if !["1", "true", "yes"].contains(&value.to_lowercase().as_str()) {
+            return;
+        }
+        self.has = true;
+        self.source = Some(source);
+    }
 }
 
 impl CliRouter {
@@ -111,6 +269,15 @@ impl CliRouter {
     /// * `shortcuts` - Vector of alternate ways to invoke the command
     /// * `value_flags` - Vector of flags that expect a value (e.g., `["--output", "--config"]`)
     ///
+    /// Values may be passed as a separate argument (`--output file.txt`), joined with an equals
+    /// sign (`--output=file.txt`), or for short flags attached directly (`-ofile.txt`).  Long
+    /// flags joined with an equals sign are recorded as value flags even when not declared.
+    ///
+    /// Suffix a value flag with `...` (e.g., `"--tag..."`) to make it repeatable, in which
+    /// case all given values can be retrieved in order via `CliRequest::get_flag_all()`.
+    /// Long value flags fall back to the environment variables derived from `env_prefix()`
+    /// when not given.
+    ///
     /// # Example
     ///
     /// ```no_run
@@ -125,21 +292,53 @@ impl CliRouter {
     /// router.add::<BuildCommand>(
     ///     "build",
     ///     vec!["b"],
-    ///     vec!["--output", "--config"]
+    ///     vec!["--output", "--config", "--tag..."]
     /// );
     /// ```
     pub fn add<T>(&mut self, alias: &str, shortcuts: Vec<&str>, value_flags: Vec<&str>)
     where
         T: CliCommand + Default + 'static,
     {
+        let cmd = Box::<T>::default();
+
+        // Get value flags, and which of them are repeatable
+        let mut repeatable_flags = vec![];
+        let mut value_flags: Vec<String> = value_flags
+            .into_iter()
+            .map(|flag| match flag.strip_suffix("...") {
+                Some(name) => {
+                    repeatable_flags.push(name.to_string());
+                    name.to_string()
+                }
+                None => flag.to_string(),
+            })
+            .collect();
+
+        // Add value flags from command specification
+        let mut flag_names = HashMap::new();
+        if let Some(spec) = cmd.spec() {
+            for flag in spec.flags.iter().filter(|f| f.is_value) {
+                let names: Vec<String> = flag.names().iter().map(|n| n.to_string()).collect();
+                for name in names.iter() {
+                    value_flags.push(name.to_string());
+                    if flag.repeatable {
+                        repeatable_flags.push(name.to_string());
+                    }
+                    flag_names.insert(name.to_string(), names.clone());
+                }
+            }
+        }
+
         // Set handler
         let handler = CliHandler {
             alias: alias.to_lowercase(),
             shortcuts: shortcuts.clone().into_iter().map(|s| s.to_string()).collect(),
-            value_flags: value_flags.clone().into_iter().map(|s| s.to_string()).collect(),
+            value_flags,
+            repeatable_flags,
+            flag_names,
         };
         self.handlers.insert(alias.to_string(), handler.clone());
-        self.commands.insert(alias.to_lowercase(), Box::<T>::default());
+        self.commands.insert(alias.to_lowercase(), cmd);
 
         // Set queue to  add
         let mut queue: Vec<String> = shortcuts.clone().into_iter().map(|s| s.to_string()).collect();
@@ -190,6 +389,398 @@ impl CliRouter {
         self.version_message = msg.to_string();
     }
 
+    /// Sets the exit codes returned by `cli_run()` when a command fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `codes` - The exit codes to use for each error type
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliRouter, CliExitCodes};
+    /// let mut router = CliRouter::new();
+    /// router.exit_codes(CliExitCodes {
+    ///     invalid_param: 2,
+    ///     ..Default::default()
+    /// });
+    /// ```
+    pub fn exit_codes(&mut self, codes: CliExitCodes) {
+        self.exit_codes = codes;
+    }
+
+    /// Sets the binary name used within generated shell completion scripts.
+    ///
+    /// Defaults to the file name of the currently running executable.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The binary name
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::CliRouter;
+    /// let mut router = CliRouter::new();
+    /// router.bin_name("myapp");
+    /// ```
+    pub fn bin_name(&mut self, name: &str) {
+        self.bin_name = name.to_string();
+    }
+
+    /// Registers the built-in `completions <SHELL>` command.
+    ///
+    /// When run via `cli_run()`, the command outputs the completion script for the
+    /// given shell (bash, zsh, fish or powershell), which users may then load within
+    /// their shell's startup file.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::CliRouter;
+    /// let mut router = CliRouter::new();
+    /// router.bin_name("myapp");
+    /// router.enable_completions();
+    /// ```
+    pub fn enable_completions(&mut self) {
+        self.add::<crate::completions::CliCompletionsCommand>("completions", vec![], vec![]);
+        self.completions_enabled = true;
+    }
+
+    /// Registers the built-in `--color <WHEN>` global flag.
+    ///
+    /// Allows users to choose when output is styled, with one of `auto`, `always` or `never`,
+    /// as per `cli_set_color()`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliRouter, CliColor, cli_style};
+    /// let mut router = CliRouter::new();
+    /// router.enable_color_flag();
+    ///
+    /// router.lookup_from(["--color=always", "help"]);
+    /// assert_eq!(cli_style("[red]Failed[/]"), "\x1b[31mFailed\x1b[0m");
+    ///
+    /// router.lookup_from(["--color", "never", "help"]);
+    /// assert_eq!(cli_style("[red]Failed[/]"), "Failed");
+    /// ```
+    pub fn enable_color_flag(&mut self) {
+        self.global(
+            "",
+            "--color",
+            true,
+            "When to use colors: auto, always or never",
+        );
+        self.color_flag = true;
+    }
+
+    /// Registers the built-in `-q|--quiet`, `-v|--verbose` and `-vv` global flags.
+    ///
+    /// The flags set the verbosity as per `cli_set_verbosity()`, with `--quiet` hiding
+    /// informational messages, `-v` displaying debug messages, and `-vv` displaying trace
+    /// messages as well.  Once registered, `-v` is no longer treated as a version flag, so the
+    /// version message is only displayed via `--version`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliRouter, CliVerbosity, cli_verbosity};
+    /// let mut router = CliRouter::new();
+    /// router.enable_verbosity_flags();
+    ///
+    /// router.lookup_from(["-vv", "help"]);
+    /// assert_eq!(cli_verbosity(), CliVerbosity::Trace);
+    ///
+    /// router.lookup_from(["--quiet", "help"]);
+    /// assert_eq!(cli_verbosity(), CliVerbosity::Quiet);
+    /// ```
+    pub fn enable_verbosity_flags(&mut self) {
+        self.global("-q", "--quiet", false, "Only display warnings and errors");
+        self.global("-v", "--verbose", false, "Display debug messages");
+        self.global("-vv", "", false, "Display debug and trace messages");
+        self.verbosity_flags = true;
+    }
+
+    /// Registers the built-in `--log-file <PATH>` global flag.
+    ///
+    /// When given, log messages are also written to the file as per `cli_set_log_file()`.
+    /// Only available with the `log` feature enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliRouter, cli_init_logger};
+    /// let mut router = CliRouter::new();
+    /// router.enable_log_file_flag();
+    /// cli_init_logger().unwrap();
+    /// ```
+    #[cfg(feature = "log")]
+    pub fn enable_log_file_flag(&mut self) {
+        self.global("", "--log-file", true, "File to also write log messages to");
+        self.log_file_flag = true;
+    }
+
+    /// Enables loading configuration files, and registers the `--config <PATH>` global flag
+    /// unless one already exists.
+    ///
+    /// Each time arguments are looked up, the files are loaded from the standard locations
+    /// as per `CliConfig::load()`, followed by the file given via `--config`.  If a file could
+    /// not be loaded, the lookup returns `CliRoute::Error` and `cli_run()` exits with the
+    /// `CliExitCodes::generic` code.  Global flags
+    /// and command value flags that were not provided on the command line or via an
+    /// environment variable then take their value from the configuration, so
+    /// `get_global()` and `CliRequest::get_flag()` return it as is.  Values for a command's
+    /// flags may be placed within a table named after the command (e.g., `[deploy]`).  Only
+    /// available with the `config` feature enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the application, used to locate its configuration files
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliRouter, CliValueSource};
+    /// let path = std::env::temp_dir().join("falcon-cli-doctest.toml");
+    /// std::fs::write(&path, "timeout = 30\n").unwrap();
+    ///
+    /// let mut router = CliRouter::new();
+    /// router.global("", "--timeout", true, "Seconds to wait");
+    /// router.enable_config("falcon-cli-doctest");
+    ///
+    /// router.lookup_from(["--config", path.to_str().unwrap(), "help"]);
+    /// assert_eq!(router.get_global("--timeout"), Some("30".to_string()));
+    /// assert_eq!(router.global_source("--timeout"), Some(CliValueSource::Config(path)));
+    /// ```
+    #[cfg(feature = "config")]
+    pub fn enable_config(&mut self, name: &str) {
+        if !self.global_flags.iter().any(|gf| gf.long == "--config") {
+            self.global("", "--config", true, "Configuration file to load");
+        }
+        self.config_name = Some(name.to_string());
+    }
+
+    /// Enables matching commands by any unique prefix of each segment.
+    ///
+    /// For example, `dom cr` runs `domain create`, as long as no other command within the
+    /// same level starts with `dom` or `cr`.  Ambiguous prefixes display the matching commands
+    /// instead, and exit with the `CliExitCodes::not_found` code.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliRouter, CliRoute, CliCommand, CliRequest, CliHelpScreen};
+    /// # #[derive(Default)]
+    /// # struct Cmd;
+    /// # impl CliCommand for Cmd {
+    /// #   fn process(&self, req: &CliRequest) -> anyhow::Result<()> { Ok(()) }
+    /// #   fn help(&self) -> CliHelpScreen { CliHelpScreen::new("", "", "") }
+    /// # }
+    /// let mut router = CliRouter::new();
+    /// router.add::<Cmd>("domain create", vec![], vec![]);
+    /// router.add::<Cmd>("domain crawl", vec![], vec![]);
+    /// router.add::<Cmd>("domain delete", vec![], vec![]);
+    /// router.enable_prefix_matching();
+    ///
+    /// let (req, _) = router.lookup_from(["dom", "del", "example.com"]).command().unwrap();
+    /// assert_eq!(req.cmd_alias, "domain delete");
+    ///
+    /// let CliRoute::Ambiguous(candidates) = router.lookup_from(["dom", "cr"]) else {
+    ///     panic!("expected ambiguous prefix");
+    /// };
+    /// assert_eq!(candidates, vec!["domain crawl", "domain create"]);
+    /// ```
+    pub fn enable_prefix_matching(&mut self) {
+        self.prefix_matching = true;
+    }
+
+    /// Sets how arguments that do not match a command, but are similar to one, are handled.
+    ///
+    /// Defaults to `CliTypoMode::Prompt`, which asks the user whether to run the most similar
+    /// command, or suggests similar commands when STDIN is not a terminal.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - How mistyped commands are handled
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliRouter, CliRoute, CliTypoMode, CliCommand, CliRequest, CliHelpScreen};
+    /// # #[derive(Default)]
+    /// # struct Cmd;
+    /// # impl CliCommand for Cmd {
+    /// #   fn process(&self, req: &CliRequest) -> anyhow::Result<()> { Ok(()) }
+    /// #   fn help(&self) -> CliHelpScreen { CliHelpScreen::new("", "", "") }
+    /// # }
+    /// let mut router = CliRouter::new();
+    /// router.add::<Cmd>("deploy", vec![], vec![]);
+    /// router.add::<Cmd>("develop", vec![], vec![]);
+    ///
+    /// router.typo_mode(CliTypoMode::Suggest(5));
+    /// let CliRoute::Suggestions(suggestions) = router.lookup_from(["deplo"]) else {
+    ///     panic!("expected suggestions");
+    /// };
+    /// assert_eq!(suggestions, vec!["deploy", "develop"]);
+    ///
+    /// router.typo_mode(CliTypoMode::AutoRun);
+    /// let (req, _) = router.lookup_from(["deplyo", "prod"]).command().unwrap();
+    /// assert_eq!((req.cmd_alias.as_str(), req.args), ("deploy", vec!["prod".to_string()]));
+    /// ```
+    pub fn typo_mode(&mut self, mode: CliTypoMode) {
+        self.typo_mode = mode;
+    }
+
+    /// Sets the prefix used to derive environment variables for flags.
+    ///
+    /// Global flags and command value flags with a long form, but no environment variable
+    /// of their own, then fall back to the variable named after the prefix and the flag,
+    /// such as `MYAPP_LOG_LEVEL` for `--log-level`.  Variables set explicitly via `env()`
+    /// take precedence.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The prefix, such as "MYAPP"
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::CliRouter;
+    /// let mut router = CliRouter::new();
+    /// router.env_prefix("doctest_prefix");
+    /// router.global("", "--log-level", true, "Minimum level to log");
+    ///
+    /// // With DOCTEST_PREFIX_LOG_LEVEL=debug set in the environment
+    /// # unsafe { std::env::set_var("DOCTEST_PREFIX_LOG_LEVEL", "debug") };
+    /// router.lookup_from(["help"]);
+    /// assert_eq!(router.get_global("--log-level"), Some("debug".to_string()));
+    /// ```
+    pub fn env_prefix(&mut self, prefix: &str) {
+        self.env_prefix = prefix.trim_end_matches('_').to_uppercase();
+    }
+
+    /// Sets the file in which `cli_repl()` saves the line history between sessions.
+    ///
+    /// History is only kept when the `repl` feature is enabled, which also provides line
+    /// editing within the shell.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Location of the history file
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::CliRouter;
+    /// let mut router = CliRouter::new();
+    /// router.history_file("/home/user/.myapp_history");
+    /// ```
+    pub fn history_file(&mut self, path: &str) {
+        self.history_file = Some(PathBuf::from(path));
+    }
+
+    /// Gets the binary name, falling back to the file name of the running executable.
+    pub(crate) fn get_bin_name(&self) -> String {
+        if !self.bin_name.is_empty() {
+            return self.bin_name.to_string();
+        }
+
+        env::args_os()
+            .next()
+            .map(PathBuf::from)
+            .and_then(|p| p.file_stem().map(|s| s.to_string_lossy().to_string()))
+            .unwrap_or_else(|| "app".to_string())
+    }
+
+    /// Gets all flags known for a command, gathered from its value flags, help screen
+    /// and specification.
+    pub(crate) fn known_flags(&self, alias: &str) -> BTreeSet<String> {
+        let mut flags = BTreeSet::new();
+        if let Some(handler) = self.handlers.values().find(|h| h.alias == alias) {
+            flags.extend(handler.value_flags.iter().cloned());
+        }
+
+        if let Some(cmd) = self.commands.get(alias) {
+            for key in cmd.help().flags.keys() {
+                flags.extend(
+                    key.split(|c: char| c == '|' || c == ',' || c.is_whitespace())
+                        .filter(|f| f.starts_with('-') && f.len() > 1)
+                        .map(|f| f.split('=').next().unwrap_or(f).to_string()),
+                );
+            }
+            if let Some(spec) = cmd.spec() {
+                for flag in spec.flags.iter() {
+                    flags.extend(flag.names().iter().map(|n| n.to_string()));
+                }
+            }
+        }
+
+        flags
+    }
+
+    /// Gets the specification of a command, including the value flags given to `add()` that
+    /// it does not declare, with environment variables derived from the prefix and, with the
+    /// `config` feature, values from the loaded configuration files.
+    pub(crate) fn command_spec(&self, cmd: &dyn CliCommand, alias: &str) -> CliCommandSpec {
+        let mut spec = cmd.spec().unwrap_or_default();
+        if let Some(handler) = self.handlers.values().find(|h| h.alias == alias) {
+            for name in handler.value_flags.iter() {
+                if spec.get_flag(name).is_some() {
+                    continue;
+                }
+                let (short, long) = match name.starts_with("--") {
+                    true => ("", name.as_str()),
+                    false => (name.as_str(), ""),
+                };
+                spec.add_value_flag(short, long, "", CliFormat::Any).repeatable =
+                    handler.repeatable_flags.contains(name);
+            }
+        }
+
+        spec.derive_env(&self.env_prefix);
+        #[cfg(feature = "config")]
+        self.config.extend_spec(&mut spec, alias);
+        spec
+    }
+
+    /// Checks the request for flags the command does not declare.
+    ///
+    /// Only checked when the command declares its flags via its help screen or specification,
+    /// and does not accept unknown flags via `CliCommand::allow_unknown_flags()`.  The value
+    /// flags given to `add()`, and the `-h` and `--help` flags, are always accepted.
+    pub(crate) fn check_flags(
+        &self,
+        cmd: &dyn CliCommand,
+        req: &CliRequest,
+    ) -> Result<(), CliError> {
+        let declared =
+            !cmd.help().flags.is_empty() || cmd.spec().is_some_and(|spec| !spec.flags.is_empty());
+        if cmd.allow_unknown_flags() || !declared {
+            return Ok(());
+        }
+        let known = self.known_flags(&req.cmd_alias);
+
+        let mut value_flags: Vec<&String> = req.flag_values.keys().collect();
+        value_flags.sort();
+        let Some(flag) = req
+            .flags
+            .iter()
+            .chain(value_flags)
+            .find(|flag| !known.contains(*flag) && !["-h", "--help"].contains(&flag.as_str()))
+        else {
+            return Ok(());
+        };
+
+        let similar = known
+            .iter()
+            .map(|chk| (levenshtein(chk, flag), chk))
+            .filter(|(distance, _)| *distance < 4)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, chk)| chk.to_string());
+        Err(CliError::UnknownFlag(flag.to_string(), similar))
+    }
+
     /// Registers a global flag available to all commands.
     ///
     /// Global flags are processed before command routing and can be checked
@@ -208,9 +799,15 @@ impl CliRouter {
     /// # use falcon_cli::CliRouter;
     /// let mut router = CliRouter::new();
     /// router.global("-v", "--verbose", false, "Enable verbose output");
-    /// router.global("-c", "--config", true, "Specify config file");
+    /// router.global("-c", "--config", true, "Specify config file").env("MYAPP_CONFIG");
     /// ```
-    pub fn global(&mut self, short: &str, long: &str, is_value: bool, desc: &str) {
+    pub fn global(
+        &mut self,
+        short: &str,
+        long: &str,
+        is_value: bool,
+        desc: &str,
+    ) -> &mut CliGlobalFlag {
         self.global_flags.push(CliGlobalFlag {
             short: short.to_string(),
             long: long.to_string(),
@@ -218,6 +815,7 @@ impl CliRouter {
             desc: desc.to_string(),
             ..Default::default()
         });
+        self.global_flags.last_mut().unwrap()
     }
 
     /// Checks if a global flag was provided.
@@ -242,7 +840,7 @@ impl CliRouter {
     /// ```
     pub fn has_global(&mut self, flag: &str) -> bool {
         if !self.parsed_global_flags {
-            self.get_raw_args();
+            self.parse_process_args();
         }
         let flag_chk = flag.to_string();
 
@@ -277,7 +875,7 @@ impl CliRouter {
     /// ```
     pub fn get_global(&mut self, flag: &str) -> Option<String> {
         if !self.parsed_global_flags {
-            self.get_raw_args();
+            self.parse_process_args();
         }
         let flag_chk = flag.to_string();
 
@@ -290,6 +888,44 @@ impl CliRouter {
         None
     }
 
+    /// Gets where a global flag was set from.
+    ///
+    /// # Arguments
+    ///
+    /// * `flag` - The flag to check (short or long form)
+    ///
+    /// # Returns
+    ///
+    /// Returns the command line, environment variable or configuration file the flag was
+    /// set from, or `None` if it was not set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliRouter, CliValueSource};
+    /// let mut router = CliRouter::new();
+    /// router.global("-c", "--config", true, "Config file").env("DOCTEST_SOURCE_CONFIG");
+    ///
+    /// router.lookup_from(["-c", "dev.toml", "help"]);
+    /// assert_eq!(router.global_source("--config"), Some(CliValueSource::CommandLine));
+    ///
+    /// // With DOCTEST_SOURCE_CONFIG=prod.toml set in the environment
+    /// # unsafe { std::env::set_var("DOCTEST_SOURCE_CONFIG", "prod.toml") };
+    /// router.lookup_from(["help"]);
+    /// let source = CliValueSource::Env("DOCTEST_SOURCE_CONFIG".to_string());
+    /// assert_eq!(router.global_source("-c"), Some(source));
+    /// ```
+    pub fn global_source(&mut self, flag: &str) -> Option<CliValueSource> {
+        if !self.parsed_global_flags {
+            self.parse_process_args();
+        }
+
+        self.global_flags
+            .iter()
+            .find(|gf| gf.short == flag || gf.long == flag)
+            .and_then(|gf| gf.source.clone())
+    }
+
     /// Adds a flag to ignore during command lookup.
     ///
     /// Ignored flags are stripped from arguments before command routing occurs.
@@ -319,43 +955,154 @@ impl CliRouter {
     ///
     /// # Returns
     ///
-    /// Returns `Some((CliRequest, &Box<dyn CliCommand>))` if a command was found,
-    /// or `None` if no command matched.
-    pub fn lookup(&mut self) -> Option<(CliRequest, &Box<dyn CliCommand>)> {
-        // Get raw args from command line, after filtering ignore flags out
-        let mut args = self.get_raw_args()?;
+    /// Returns the routing outcome, being either a command along with its parsed request,
+    /// a request for help or the version, or that no command matched.
+    pub fn lookup(&mut self) -> CliRoute<'_> {
+        self.lookup_from(env::args_os().skip(1))
+    }
+
+    /// Looks up and routes to the appropriate command handler using the given arguments.
+    ///
+    /// Identical to `lookup()`, except the arguments are taken from the provided iterator
+    /// instead of the process command line.  The arguments should not include the
+    /// program name.  Global flags are reset and re-parsed from these arguments, so
+    /// `has_global()` and `get_global()` reflect the most recent call.
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - The arguments to route, excluding the program name
+    ///
+    /// # Returns
+    ///
+    /// Returns the routing outcome, being either a command along with its parsed request,
+    /// a request for help or the version, or that no command matched.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliRouter, CliRoute, CliCommand, CliRequest, CliHelpScreen};
+    /// # #[derive(Default)]
+    /// # struct BuildCommand;
+    /// # impl CliCommand for BuildCommand {
+    /// #   fn process(&self, req: &CliRequest) -> anyhow::Result<()> { Ok(()) }
+    /// #   fn help(&self) -> CliHelpScreen { CliHelpScreen::new("", "", "") }
+    /// # }
+    /// let mut router = CliRouter::new();
+    /// router.version_message("Build v1.0");
+    /// router.global("-c", "--config", true, "Config file");
+    /// router.add::<BuildCommand>("build", vec!["b"], vec!["--output"]);
+    ///
+    /// let (req, _) = router.lookup_from(["b", "app", "--output", "out.txt", "-c", "dev.toml"]).command().unwrap();
+    /// assert_eq!(req.cmd_alias, "build");
+    /// assert_eq!(req.args, vec!["app"]);
+    /// assert_eq!(req.get_flag("--output"), Some("out.txt".to_string()));
+    /// assert_eq!(router.get_global("--config"), Some("dev.toml".to_string()));
+    ///
+    /// let (req, _) = router.lookup_from(["build", "--output=out.txt", "--config=prod.toml"]).command().unwrap();
+    /// assert_eq!(req.get_flag("--output"), Some("out.txt".to_string()));
+    /// assert_eq!(router.get_global("--config"), Some("prod.toml".to_string()));
+    ///
+    /// // Everything after `--` is passed through verbatim
+    /// let (req, _) = router.lookup_from(["build", "app", "--", "-5", "--config", "x"]).command().unwrap();
+    /// assert_eq!(req.args, vec!["app"]);
+    /// assert_eq!(req.trailing_args, vec!["-5", "--config", "x"]);
+    /// assert_eq!(router.get_global("--config"), None);
+    ///
+    /// // Help and version
+    /// assert!(matches!(router.lookup_from(["help"]), CliRoute::Help(None)));
+    /// assert!(matches!(router.lookup_from(["--version"]), CliRoute::Version(_)));
+    /// ```
+    pub fn lookup_from<I>(&mut self, args: I) -> CliRoute<'_>
+    where
+        I: IntoIterator,
+        I::Item: Into<OsString>,
+    {
+        // Get raw args, after filtering global and ignore flags out
+        let args = self.parse_args(args);
+        self.route_args(args)
+    }
+
+    /// Routes arguments whose global flags have already been parsed via `parse_args()`.
+    pub(crate) fn route_args(&mut self, args: Option<Vec<String>>) -> CliRoute<'_> {
+        #[cfg(feature = "config")]
+        if let Err(e) = self.apply_config() {
+            return CliRoute::Error(e);
+        }
+        self.apply_builtin_flags();
+        let Some(mut args) = args else {
+            return CliRoute::Help(None);
+        };
+
+        // Check for version
+        if !self.version_message.is_empty()
+            && args
+                .iter()
+                .take_while(|arg| *arg != "--")
+                .any(|arg| ["-v", "--version"].contains(&arg.as_str()))
+        {
+            return CliRoute::Version(self.version_message.to_string());
+        }
 
         // Check for help
+        let is_help = ["help", "-h"].contains(&args[0].as_str());
+        if is_help {
+            args.remove(0);
+            let cat_alias = args.join(" ");
+            if args.is_empty() {
+                return CliRoute::Help(None);
+            } else if self.categories.contains_key(&cat_alias) {
+                return CliRoute::Help(Some(cat_alias));
+            }
+        }
 
-        let is_help = self.is_help(&mut args);
         // Lookup handler
-        let handler = self.lookup_handler(&mut args)?;
+        let handler = match self.lookup_handler(&mut args) {
+            Ok(handler) => handler,
+            Err(LookupFailure::Suggestions(suggestions)) if suggestions.is_empty() => {
+                return CliRoute::NotFound;
+            }
+            Err(LookupFailure::Suggestions(suggestions)) => {
+                return CliRoute::Suggestions(suggestions);
+            }
+            Err(LookupFailure::Ambiguous(candidates)) => return CliRoute::Ambiguous(candidates),
+        };
 
         // Gather flags
-        let (flags, flag_values) = self.gather_flags(&mut args, &handler);
-
-        // Return
-        let req = CliRequest {
-            cmd_alias: handler.alias.to_string(),
-            is_help,
-            args,
-            flags,
-            flag_values,
-            shortcuts: handler.shortcuts.to_vec(),
-        };
+        let (mut req, _) = self.gather_flags(args, &handler);
+        req.is_help = is_help;
 
         let cmd = self.commands.get(&handler.alias).unwrap();
-        Some((req, cmd))
+        CliRoute::Command(req, cmd.as_ref())
     }
 
-    fn get_raw_args(&mut self) -> Option<Vec<String>> {
+    pub(crate) fn parse_args<I>(&mut self, args: I) -> Option<Vec<String>>
+    where
+        I: IntoIterator,
+        I::Item: Into<OsString>,
+    {
         let mut cmd_args = vec![];
-        let mut skip_next = true;
+        let mut skip_next = false;
         let mut global_value_index: Option<usize> = None;
         self.parsed_global_flags = true;
 
-        for value in env::args() {
-            if skip_next {
+        // Reset any previously parsed global flags
+        for gf in self.global_flags.iter_mut() {
+            gf.has = false;
+            gf.value = None;
+            gf.source = None;
+        }
+
+        let mut end_of_options = false;
+        for arg in args {
+            let value = arg.into().to_string_lossy().to_string();
+            if end_of_options {
+                cmd_args.push(value);
+                continue;
+            } else if value == "--" {
+                end_of_options = true;
+                cmd_args.push(value);
+                continue;
+            } else if skip_next {
                 skip_next = false;
                 if let Some(index) = global_value_index {
                     self.global_flags[index].value = Some(value.to_string());
@@ -364,18 +1111,19 @@ impl CliRouter {
                 continue;
             }
 
-            if ["-v", "--version"].contains(&value.as_str()) && !self.version_message.is_empty() {
-                println!("{}", self.version_message);
-                std::process::exit(0);
-            } else if let Some(is_value) = self.ignore_flags.get(&value) {
+            if let Some(is_value) = self.ignore_flags.get(&value) {
                 skip_next = *is_value;
-            } else if let Some(index) = self
-                .global_flags
-                .iter()
-                .position(|gf| [gf.short.to_string(), gf.long.to_string()].contains(&value))
+            } else if split_long_flag(&value)
+                .is_some_and(|(flag, _)| self.ignore_flags.get(flag) == Some(&true))
             {
-                skip_next = self.global_flags[index].is_value;
-                if skip_next {
+                continue;
+            } else if let Some((index, flag_value)) = self.match_global(&value) {
+                self.global_flags[index].has = true;
+                self.global_flags[index].source = Some(CliValueSource::CommandLine);
+                if flag_value.is_some() {
+                    self.global_flags[index].value = flag_value;
+                } else if self.global_flags[index].is_value {
+                    skip_next = true;
                     global_value_index = Some(index);
                 }
             } else {
@@ -383,6 +1131,25 @@ impl CliRouter {
             }
         }
 
+        // Fall back to the session, then environment variables
+        for gf in self.global_flags.iter_mut().filter(|gf| !gf.has) {
+            if let Some(kept) =
+                self.session_flags.iter().find(|s| s.short == gf.short && s.long == gf.long)
+            {
+                gf.has = true;
+                gf.value = kept.value.clone();
+                gf.source = kept.source.clone();
+                continue;
+            }
+
+            let Some(name) = env_name(&self.env_prefix, &gf.env, &gf.long) else {
+                continue;
+            };
+            if let Ok(value) = env::var(&name) {
+                gf.set_fallback(value, CliValueSource::Env(name));
+            }
+        }
+
         if !cmd_args.is_empty() {
             Some(cmd_args)
         } else {
@@ -390,43 +1157,158 @@ impl CliRouter {
         }
     }
 
-    /// Check for help being requested
-    fn is_help(&self, args: &mut Vec<String>) -> bool {
-        let mut is_help = false;
-        if ["help", "-h"].contains(&args[0].as_str()) {
-            is_help = true;
-            args.remove(0);
+    /// Parses the global flags from the process command line, when they are accessed before
+    /// a lookup.
+    fn parse_process_args(&mut self) {
+        self.parse_args(env::args_os().skip(1));
 
-            if args.is_empty() {
-                CliHelpScreen::render_index(self);
+        // Errors are reported once the arguments are routed
+        #[cfg(feature = "config")]
+        let _ = self.apply_config();
+    }
+
+    /// Loads the configuration files, and sets global flags that were not given from them.
+    #[cfg(feature = "config")]
+    fn apply_config(&mut self) -> Result<(), CliError> {
+        let Some(name) = &self.config_name else {
+            return Ok(());
+        };
+        let path = self
+            .global_flags
+            .iter()
+            .find(|gf| gf.long == "--config")
+            .and_then(|gf| gf.value.as_deref());
+
+        self.config = CliConfig::load(name, path)?;
+        for gf in self.global_flags.iter_mut().filter(|gf| !gf.has) {
+            if let Some((value, file)) = self.config.lookup("", &gf.long) {
+                gf.set_fallback(value.to_string(), CliValueSource::Config(file.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the built-in `--color`, verbosity and `--log-file` global flags, if they were
+    /// given.
+    fn apply_builtin_flags(&self) {
+        let given = |name: &str| {
+            self.global_flags.iter().find(|gf| gf.has && (gf.short == name || gf.long == name))
+        };
+
+        if self.color_flag
+            && let Some(value) = given("--color").and_then(|gf| gf.value.as_ref())
+        {
+            match value.parse::<CliColor>() {
+                Ok(color) => cli_set_color(color),
+                Err(e) => cli_error_line(&e),
             }
+        }
 
-            // Check category help
-            let cat_alias = args.join(" ").to_string();
-            if self.categories.contains_key(&cat_alias) {
-                CliHelpScreen::render_category(&self, &cat_alias);
+        if self.verbosity_flags {
+            if given("-q").is_some() {
+                cli_set_verbosity(CliVerbosity::Quiet);
+            } else if given("-vv").is_some() {
+                cli_set_verbosity(CliVerbosity::Trace);
+            } else if given("-v").is_some() {
+                cli_set_verbosity(CliVerbosity::Verbose);
             }
         }
 
-        is_help
+        #[cfg(feature = "log")]
+        if self.log_file_flag
+            && let Some(path) = given("--log-file").and_then(|gf| gf.value.as_ref())
+            && let Err(e) = cli_set_log_file(path)
+        {
+            cli_error_line(&format!("Unable to open log file {}: {}", path, e));
+        }
+    }
+
+    /// Matches an argument against the registered global flags.
+    ///
+    /// Returns the index of the matching global flag, along with its value if it was
+    /// attached to the flag itself (eg. `--config=file.toml` or `-cfile.toml`).
+    fn match_global(&self, value: &str) -> Option<(usize, Option<String>)> {
+        if value.is_empty() {
+            return None;
+        } else if let Some(index) =
+            self.global_flags.iter().position(|gf| gf.short == value || gf.long == value)
+        {
+            return Some((index, None));
+        }
+
+        // Long flag with attached value
+        if let Some((flag, flag_value)) = split_long_flag(value)
+            && let Some(index) =
+                self.global_flags.iter().position(|gf| gf.is_value && gf.long == flag)
+        {
+            return Some((index, Some(flag_value.to_string())));
+        }
+
+        // Short flag with attached value
+        if value.starts_with("--") {
+            return None;
+        }
+        let index = self.global_flags.iter().position(|gf| {
+            gf.is_value && gf.short.len() == 2 && value.len() > 2 && value.starts_with(&gf.short)
+        })?;
+        Some((index, Some(value[2..].to_string())))
     }
 
-    fn lookup_handler(&self, args: &mut Vec<String>) -> Option<CliHandler> {
+    /// Looks up the handler for the arguments, or returns the route to take instead.
+    fn lookup_handler(&self, args: &mut Vec<String>) -> Result<CliHandler, LookupFailure> {
+        let h_alias = match self.match_handler(args) {
+            Ok(Some((alias, start, length))) => {
+                args.drain(start..start + length);
+                alias
+            }
+            // Check for typos, if none
+            Ok(None) => self.lookup_similar(args).map_err(LookupFailure::Suggestions)?,
+            Err(candidates) => return Err(LookupFailure::Ambiguous(candidates)),
+        };
+
+        let handler = self.handlers.get(&h_alias).ok_or(LookupFailure::Suggestions(vec![]))?;
+        Ok(handler.clone())
+    }
+
+    /// Walks the children tree for a command match, returning the handler alias along with
+    /// the start position and number of arguments making up the command.
+    ///
+    /// With prefix matching enabled, segments may also be unique prefixes, and the commands
+    /// matching an ambiguous prefix are returned as the error.
+    pub(crate) fn match_handler(
+        &self,
+        args: &[String],
+    ) -> Result<Option<(String, usize, usize)>, Vec<String>> {
         let mut h_alias: Option<String> = None;
         let (mut start, mut length) = (0, 0);
+        let mut path: Vec<String> = vec![];
 
         let mut child = self;
         for (pos, segment) in args.iter().enumerate() {
-            if segment.starts_with("-") {
+            if segment == "--" {
+                break;
+            } else if segment.starts_with("-") {
                 continue;
             }
 
-            if let Some(next) = child.children.get(&segment.to_lowercase()) {
+            let next = match child.match_child(&segment.to_lowercase(), self.prefix_matching) {
+                Ok(next) => next,
+                Err(keys) if h_alias.is_none() => {
+                    return Err(keys
+                        .iter()
+                        .map(|key| [path.join(" "), key.to_string()].join(" ").trim().to_string())
+                        .collect());
+                }
+                Err(_) => None,
+            };
+
+            if let Some((key, next)) = next {
                 if length == 0 {
                     (start, length) = (pos, 1);
                 } else {
                     length += 1;
                 }
+                path.push(key);
 
                 if let Some(h_child) = &next.handler_alias {
                     h_alias = Some(h_child.clone());
@@ -437,58 +1319,132 @@ impl CliRouter {
             } else {
                 child = self;
                 length = 0;
+                path.clear();
             }
         }
 
-        // Check for typos, if none
-        if h_alias.is_none() {
-            h_alias = self.lookup_similar(args);
-        } else if h_alias.is_some() {
-            args.drain(start..start + length);
-        } else {
-            return None;
+        Ok(h_alias.map(|alias| (alias, start, length)))
+    }
+
+    /// Gets the child node matching a segment, falling back to a unique prefix of a child
+    /// if enabled.  Returns the sorted keys of all matching children if the prefix is
+    /// ambiguous.
+    fn match_child(
+        &self,
+        segment: &str,
+        prefix: bool,
+    ) -> Result<Option<(String, &CliRouter)>, Vec<String>> {
+        if let Some(next) = self.children.get(segment) {
+            return Ok(Some((segment.to_string(), next)));
+        } else if !prefix || segment.is_empty() {
+            return Ok(None);
+        }
+
+        let mut matches: Vec<(&String, &Box<CliRouter>)> =
+            self.children.iter().filter(|(key, _)| key.starts_with(segment)).collect();
+        matches.sort_by(|a, b| a.0.cmp(b.0));
+
+        // Skip shortcuts, which only lead to commands another match also leads to
+        let handlers: Vec<BTreeSet<String>> =
+            matches.iter().map(|(_, next)| next.subtree_handlers()).collect();
+        let mut matches: Vec<(&String, &Box<CliRouter>)> = matches
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| {
+                !handlers.iter().enumerate().any(|(j, other)| {
+                    *i != j && handlers[*i].is_subset(other) && (handlers[*i] != *other || j < *i)
+                })
+            })
+            .map(|(_, child)| child)
+            .collect();
+
+        if matches.len() > 1 {
+            return Err(matches.into_iter().map(|(key, _)| key.to_string()).collect());
         }
+        Ok(matches.pop().map(|(key, next)| (key.to_string(), next.as_ref())))
+    }
 
-        let handler = self.handlers.get(&h_alias?)?;
-        Some(handler.clone())
+    /// Gets the aliases of all handlers within this node and its children.
+    fn subtree_handlers(&self) -> BTreeSet<String> {
+        let mut handlers: BTreeSet<String> = self.handler_alias.iter().cloned().collect();
+        for child in self.children.values() {
+            handlers.extend(child.subtree_handlers());
+        }
+        handlers
     }
 
-    fn gather_flags(
+    /// Gathers the parameters and flags into a request, also returning any trailing value
+    /// flag that was given without a value.
+    pub(crate) fn gather_flags(
         &self,
-        args: &mut Vec<String>,
+        args: Vec<String>,
         handler: &CliHandler,
-    ) -> (Vec<String>, HashMap<String, String>) {
-        let mut incl_value = false;
-        let mut flags = vec![];
-        let mut flag_values: HashMap<String, String> = HashMap::new();
-        let mut final_args = vec![];
+    ) -> (CliRequest, Option<String>) {
+        let mut req = CliRequest {
+            cmd_alias: handler.alias.to_string(),
+            shortcuts: handler.shortcuts.to_vec(),
+            ..Default::default()
+        };
+        let mut value_flag: Option<String> = None;
 
         // Iterate over args
         for (pos, value) in args.iter().enumerate() {
-            if incl_value {
-                flag_values.insert(args[pos - 1].to_string(), value.to_string());
-                incl_value = false;
-            } else if value.starts_with("-") && handler.value_flags.contains(&value) {
-                incl_value = true;
+            if value == "--" {
+                // Ends options, even when a value flag is awaiting its value
+                req.trailing_args = args[pos + 1..].to_vec();
+                value_flag = None;
+                break;
+            } else if let Some(flag) = value_flag.take() {
+                self.add_flag_value(&mut req, handler, &flag, value);
+            } else if let Some((flag, flag_value)) = split_long_flag(value) {
+                // Recorded as a value flag even if undeclared, as the value is explicit
+                self.add_flag_value(&mut req, handler, flag, flag_value);
+            } else if value.starts_with("-") && handler.value_flags.contains(value) {
+                value_flag = Some(value.to_string());
             } else if value.starts_with("--") {
-                flags.push(value.to_string());
-            } else if value.starts_with("-") {
-                for char in value[1..].chars() {
-                    flags.push(format!("-{}", char));
+                req.flags.push(value.to_string());
+            } else if let Some(short) = value.strip_prefix("-") {
+                // Combined short flags, with anything after a value flag being its value (eg. -vofile.txt)
+                for (pos, char) in short.char_indices() {
+                    let flag = format!("-{}", char);
+                    if handler.value_flags.contains(&flag) {
+                        let flag_value = &short[pos + char.len_utf8()..];
+                        if flag_value.is_empty() {
+                            value_flag = Some(flag);
+                        } else {
+                            self.add_flag_value(&mut req, handler, &flag, flag_value);
+                        }
+                        break;
+                    }
+                    req.flags.push(flag);
                 }
             } else {
-                final_args.push(value.to_string());
+                req.args.push(value.to_string());
             }
         }
 
-        *args = final_args;
-        (flags, flag_values)
+        (req, value_flag)
+    }
+
+    /// Records the value of a flag under all of its names, appending to its list if the flag is repeatable.
+    fn add_flag_value(&self, req: &mut CliRequest, handler: &CliHandler, flag: &str, value: &str) {
+        let names = match handler.flag_names.get(flag) {
+            Some(names) => names.clone(),
+            None => vec![flag.to_string()],
+        };
+
+        for name in names {
+            if handler.repeatable_flags.contains(&name) {
+                req.flag_lists.entry(name.to_string()).or_default().push(value.to_string());
+            }
+            req.flag_values.insert(name, value.to_string());
+        }
     }
 
     /// Attempts to find a similar command when an exact match isn't found.
     ///
     /// Uses Levenshtein distance to find commands that closely resemble the input,
-    /// handling potential typos. If a close match is found, prompts the user for confirmation.
+    /// handling potential typos, which are then handled as per the router's `CliTypoMode`.
     /// This method is called automatically by `lookup()`.
     ///
     /// # Arguments
@@ -497,56 +1453,63 @@ impl CliRouter {
     ///
     /// # Returns
     ///
-    /// Returns `Some(String)` with the corrected command name if found and confirmed,
-    /// or `None` otherwise.
-    fn lookup_similar(&self, args: &mut Vec<String>) -> Option<String> {
+    /// Returns `Ok(String)` with the corrected command name if found and confirmed, or
+    /// `Err` with the similar commands to suggest, which is empty if there are none to
+    /// suggest.
+    fn lookup_similar(&self, args: &mut Vec<String>) -> Result<String, Vec<String>> {
         let start = args.iter().position(|a| !a.starts_with("-")).unwrap_or(0);
-        let search_args =
-            args.clone().into_iter().filter(|a| !a.starts_with("-")).collect::<Vec<String>>();
-
-        // Get available commands to search
-        let mut commands: Vec<String> = self.commands.keys().map(|c| c.to_string()).collect();
-        commands.sort_by(|a, b| {
-            let a_count = a.chars().filter(|c| c.is_whitespace()).count();
-            let b_count = b.chars().filter(|c| c.is_whitespace()).count();
-            b_count.cmp(&a_count)
-        });
-        let (mut distance, mut bin_length, mut found_cmd) = (0, 0, String::new());
+        let search_args = args
+            .iter()
+            .take_while(|a| *a != "--")
+            .filter(|a| !a.starts_with("-"))
+            .cloned()
+            .collect::<Vec<String>>();
 
-        // Go through commands
-        for chk_alias in commands {
+        // Rank commands by distance, preferring those with more words
+        let mut similar: Vec<(usize, usize, String)> = vec![];
+        for chk_alias in self.commands.keys() {
             let length = chk_alias.chars().filter(|c| c.is_whitespace()).count() + 1;
+            let end = search_args.len().min(length);
+            let search_str = search_args[..end].join(" ");
+
+            let distance = levenshtein(chk_alias, &search_str);
+            if distance > 0 && distance < 4 {
+                similar.push((distance, length, chk_alias.to_string()));
+            }
+        }
+        similar.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)).then(a.2.cmp(&b.2)));
+        let Some((_, length, found_cmd)) = similar.first().cloned() else {
+            return Err(vec![]);
+        };
 
-            // Check lowest distance, if we're completed a bin
-            if bin_length != length && bin_length > 0 && distance > 0 && distance < 4 {
+        let mode = match self.typo_mode {
+            CliTypoMode::Prompt if !stdin_is_interactive() => CliTypoMode::Suggest(3),
+            mode => mode,
+        };
+        match mode {
+            CliTypoMode::Prompt => {
                 let confirm_msg = format!(
                     "No command with that name exists, but a similar command with the name '{}' does exist.  Is this the command you wish to run?",
                     found_cmd
                 );
-                if cli_confirm(&confirm_msg) {
-                    let end = (start + length).min(args.len());
-                    args.drain(start..end);
-                    return Some(found_cmd);
-                } else {
-                    return None;
+                if !cli_confirm(&confirm_msg) {
+                    return Err(vec![]);
                 }
-            } else if bin_length != length {
-                bin_length = length;
-                distance = 0;
-                found_cmd = String::new();
             }
-
-            let end = search_args.len().min(length);
-            let search_str = search_args[..end].join(" ").to_string();
-
-            let chk_distance = levenshtein(&chk_alias, &search_str);
-            if chk_distance < distance || distance == 0 {
-                distance = chk_distance;
-                found_cmd = chk_alias.to_string();
+            CliTypoMode::Suggest(limit) => {
+                return Err(similar.into_iter().take(limit).map(|(_, _, alias)| alias).collect());
+            }
+            CliTypoMode::AutoRun => {
+                cli_warn!(
+                    "No command with that name exists, running '{}' instead",
+                    found_cmd
+                );
             }
         }
 
-        None
+        let end = (start + length).min(args.len());
+        args.drain(start..end);
+        Ok(found_cmd)
     }
 
     /// Adds a category for organizing related commands.
@@ -579,3 +1542,332 @@ impl CliRouter {
         );
     }
 }
+
+/// Gets the environment variable of a flag, either set explicitly, or derived from the
+/// prefix and the flag's long form (eg. `MYAPP_LOG_LEVEL` for `--log-level`).
+pub(crate) fn env_name(prefix: &str, explicit: &Option<String>, long: &str) -> Option<String> {
+    if explicit.is_some() {
+        return explicit.clone();
+    } else if prefix.is_empty() || long.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "{}_{}",
+        prefix,
+        long.trim_start_matches('-').replace('-', "_").to_uppercase()
+    ))
+}
+
+/// Splits a long flag with an attached value (eg. `--output=file.txt`) into its name and value.
+fn split_long_flag(value: &str) -> Option<(&str, &str)> {
+    if !value.starts_with("--") {
+        return None;
+    }
+    value.split_once('=')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct TestCommand;
+
+    impl CliCommand for TestCommand {
+        fn process(&self, _req: &CliRequest) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn help(&self) -> CliHelpScreen {
+            CliHelpScreen::new("Test", "", "")
+        }
+    }
+
+    fn lookup(router: &mut CliRouter, args: &[&str]) -> CliRequest {
+        router.lookup_from(args.iter().copied()).command().expect("command not found").0
+    }
+
+    fn build_router(value_flags: Vec<&str>) -> CliRouter {
+        let mut router = CliRouter::new();
+        router.add::<TestCommand>("build", vec![], value_flags);
+        router
+    }
+
+    #[test]
+    fn long_flag_with_attached_value() {
+        let mut router = build_router(vec!["--output", "--define"]);
+        let req = lookup(
+            &mut router,
+            &["build", "--output=out.txt", "--define=a=b", "app"],
+        );
+        assert_eq!(req.get_flag("--output"), Some("out.txt".to_string()));
+        assert_eq!(req.get_flag("--define"), Some("a=b".to_string()));
+        assert_eq!(req.args, vec!["app"]);
+        assert!(req.flags.is_empty());
+    }
+
+    #[test]
+    fn undeclared_long_flag_with_attached_value() {
+        let mut router = build_router(vec![]);
+        let req = lookup(&mut router, &["build", "--mode=fast", "--force"]);
+        assert_eq!(req.get_flag("--mode"), Some("fast".to_string()));
+        assert_eq!(req.flags, vec!["--force"]);
+    }
+
+    #[test]
+    fn short_flag_with_attached_value() {
+        let mut router = build_router(vec!["-o"]);
+        let req = lookup(&mut router, &["build", "-oout.txt"]);
+        assert_eq!(req.get_flag("-o"), Some("out.txt".to_string()));
+
+        let req = lookup(&mut router, &["build", "-vfofile.txt"]);
+        assert_eq!(req.flags, vec!["-v", "-f"]);
+        assert_eq!(req.get_flag("-o"), Some("file.txt".to_string()));
+
+        let req = lookup(&mut router, &["build", "-o", "-5"]);
+        assert_eq!(req.get_flag("-o"), Some("-5".to_string()));
+    }
+
+    #[test]
+    fn global_flag_with_attached_value() {
+        let mut router = build_router(vec![]);
+        router.global("-c", "--config", true, "Config file");
+
+        lookup(&mut router, &["--config=dev.toml", "build"]);
+        assert_eq!(router.get_global("--config"), Some("dev.toml".to_string()));
+
+        lookup(&mut router, &["build", "-cprod.toml"]);
+        assert_eq!(router.get_global("-c"), Some("prod.toml".to_string()));
+    }
+
+    #[test]
+    fn end_of_options_separator() {
+        let mut router = build_router(vec!["--output"]);
+        let req = lookup(
+            &mut router,
+            &["build", "-v", "app", "--", "-5", "--output", "x"],
+        );
+        assert_eq!(req.flags, vec!["-v"]);
+        assert_eq!(req.args, vec!["app"]);
+        assert_eq!(req.trailing_args, vec!["-5", "--output", "x"]);
+        assert_eq!(req.get_flag("--output"), None);
+    }
+
+    #[test]
+    fn value_flag_before_separator() {
+        let mut router = build_router(vec!["--output"]);
+        let req = lookup(&mut router, &["build", "--output", "--", "file.txt"]);
+        assert_eq!(req.get_flag("--output"), None);
+        assert_eq!(req.trailing_args, vec!["file.txt"]);
+    }
+
+    #[test]
+    fn global_value_flag_before_separator() {
+        let mut router = build_router(vec![]);
+        router.global("-c", "--config", true, "Config file");
+        let req = lookup(&mut router, &["build", "--config", "--", "-c"]);
+        assert!(router.has_global("--config"));
+        assert_eq!(router.get_global("--config"), None);
+        assert_eq!(req.trailing_args, vec!["-c"]);
+    }
+
+    #[test]
+    fn repeatable_value_flags() {
+        let mut router = build_router(vec!["--tag...", "--output"]);
+        let req = lookup(
+            &mut router,
+            &[
+                "build",
+                "--tag",
+                "a",
+                "--tag=b",
+                "--output",
+                "x",
+                "--output=y",
+            ],
+        );
+        assert_eq!(req.get_flag_all("--tag"), vec!["a", "b"]);
+        assert_eq!(req.get_flag("--tag"), Some("b".to_string()));
+        assert_eq!(req.get_flag_all("--output"), vec!["y"]);
+    }
+
+    #[test]
+    fn repeated_boolean_flags() {
+        let mut router = build_router(vec![]);
+        let req = lookup(&mut router, &["build", "-vvv", "--force", "--force"]);
+        assert_eq!(req.get_flag_count("-v"), 3);
+        assert_eq!(req.get_flag_count("--force"), 2);
+        assert_eq!(req.get_flag_count("-q"), 0);
+    }
+
+    #[cfg(feature = "mock")]
+    #[test]
+    fn unmatched_command_exit_code() {
+        use crate::testing;
+
+        let mut router = build_router(vec![]);
+        router.typo_mode(CliTypoMode::Prompt);
+        assert_eq!(testing::run(&mut router, Vec::<String>::new()).exit_code, 0);
+        assert_eq!(testing::run(&mut router, ["zzzz"]).exit_code, 64);
+
+        testing::queue_answers(["n"]);
+        assert_eq!(testing::run(&mut router, ["buil"]).exit_code, 64);
+        testing::clear_answers();
+    }
+
+    #[derive(Default)]
+    struct DeclaredCommand;
+
+    impl CliCommand for DeclaredCommand {
+        fn process(&self, _req: &CliRequest) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn help(&self) -> CliHelpScreen {
+            let mut help = CliHelpScreen::new("Deploy", "deploy", "");
+            help.add_flag("--force|-f", "Force the deploy");
+            help.add_flag("--region", "Region to deploy to");
+            help
+        }
+    }
+
+    #[test]
+    fn value_flags_alone_accept_other_flags() {
+        let mut router = build_router(vec!["--output"]);
+        let req = lookup(
+            &mut router,
+            &["build", "--output", "a.txt", "--verbose", "--mode=x"],
+        );
+        let cmd = router.commands["build"].as_ref();
+        assert!(router.check_flags(cmd, &req).is_ok());
+    }
+
+    #[test]
+    fn declared_flags_reject_unknown_flags() {
+        let mut router = CliRouter::new();
+        router.add::<DeclaredCommand>("deploy", vec![], vec!["--region"]);
+
+        let req = lookup(&mut router, &["deploy", "-f", "--region", "us", "--help"]);
+        let cmd = router.commands["deploy"].as_ref();
+        assert!(router.check_flags(cmd, &req).is_ok());
+
+        let req = lookup(&mut router, &["deploy", "--forse"]);
+        let cmd = router.commands["deploy"].as_ref();
+        let err = router.check_flags(cmd, &req).unwrap_err();
+        assert!(matches!(
+            err,
+            CliError::UnknownFlag(flag, Some(similar)) if flag == "--forse" && similar == "--force"
+        ));
+    }
+
+    fn prefix_router() -> CliRouter {
+        let mut router = CliRouter::new();
+        router.add::<TestCommand>("domain create", vec![], vec![]);
+        router.add::<TestCommand>("domain crawl", vec![], vec![]);
+        router.add::<TestCommand>("domain delete", vec![], vec![]);
+        router.add::<TestCommand>("deploy", vec![], vec![]);
+        router.add::<TestCommand>("deployment list", vec![], vec![]);
+        router.typo_mode(CliTypoMode::Suggest(3));
+        router.enable_prefix_matching();
+        router
+    }
+
+    #[test]
+    fn unique_prefix() {
+        let mut router = prefix_router();
+        let req = lookup(&mut router, &["dom", "del", "example.com", "--force"]);
+        assert_eq!(req.cmd_alias, "domain delete");
+        assert_eq!(req.args, vec!["example.com"]);
+        assert_eq!(req.flags, vec!["--force"]);
+
+        let req = lookup(&mut router, &["domain", "cre"]);
+        assert_eq!(req.cmd_alias, "domain create");
+    }
+
+    #[test]
+    fn exact_match_preferred_over_prefix() {
+        let mut router = prefix_router();
+        let req = lookup(&mut router, &["deploy", "prod"]);
+        assert_eq!(req.cmd_alias, "deploy");
+        assert_eq!(req.args, vec!["prod"]);
+    }
+
+    #[test]
+    fn ambiguous_prefix() {
+        let mut router = prefix_router();
+        let CliRoute::Ambiguous(candidates) = router.lookup_from(["dom", "cr"]) else {
+            panic!("expected ambiguous prefix");
+        };
+        assert_eq!(candidates, vec!["domain crawl", "domain create"]);
+    }
+
+    #[test]
+    fn prefix_matching_disabled() {
+        let mut router = prefix_router();
+        router.prefix_matching = false;
+        assert!(router.lookup_from(["dom", "del"]).command().is_none());
+    }
+
+    #[test]
+    fn value_flags_fall_back_to_env() {
+        let mut router = CliRouter::new();
+        router.env_prefix("falcon_router_test");
+        router.add::<DeclaredCommand>("deploy", vec![], vec!["--region", "-t..."]);
+        unsafe { env::set_var("FALCON_ROUTER_TEST_REGION", "eu-west") };
+
+        let mut req = lookup(&mut router, &["deploy", "-t", "a", "-t", "b"]);
+        let cmd = router.commands["deploy"].as_ref();
+        router.command_spec(cmd, "deploy").apply(&mut req).unwrap();
+        assert_eq!(req.get_flag("--region"), Some("eu-west".to_string()));
+        assert_eq!(
+            req.get_flag_source("--region"),
+            Some(CliValueSource::Env("FALCON_ROUTER_TEST_REGION".to_string()))
+        );
+        assert_eq!(req.get_flag_all("-t"), vec!["a", "b"]);
+
+        let help = CliHelpScreen::format(&router, "deploy");
+        assert!(help.contains("Region to deploy to [env: FALCON_ROUTER_TEST_REGION]\n"));
+        assert!(!help.contains("-t "));
+        unsafe { env::remove_var("FALCON_ROUTER_TEST_REGION") };
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn value_flags_fall_back_to_config() {
+        let path = env::temp_dir().join("falcon-router-test.toml");
+        std::fs::write(&path, "output = \"a.txt\"\n\n[build]\noutput = \"b.txt\"\n").unwrap();
+
+        let mut router = build_router(vec!["--output"]);
+        router.enable_config("falcon-router-test");
+        let mut req = lookup(&mut router, &["build", "--config", path.to_str().unwrap()]);
+        let cmd = router.commands["build"].as_ref();
+        router.command_spec(cmd, "build").apply(&mut req).unwrap();
+        assert_eq!(req.get_flag("--output"), Some("b.txt".to_string()));
+        assert_eq!(
+            req.get_flag_source("--output"),
+            Some(CliValueSource::Config(path.clone()))
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn invalid_config_fails_lookup() {
+        let path = env::temp_dir().join("falcon-router-test-invalid.toml");
+        std::fs::write(&path, "output = \n").unwrap();
+
+        let mut router = build_router(vec![]);
+        router.enable_config("falcon-router-test");
+        let args = ["build", "--config", path.to_str().unwrap()];
+        assert!(matches!(
+            router.lookup_from(args),
+            CliRoute::Error(CliError::Generic(_))
+        ));
+        #[cfg(feature = "mock")]
+        assert_eq!(crate::testing::run(&mut router, args).exit_code, 1);
+
+        let args = ["build", "--config", "/nonexistent/falcon-router-test.toml"];
+        assert!(matches!(router.lookup_from(args), CliRoute::Error(_)));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,523 @@
+// Copyright 2025 Aquila Labs of Alberta, Canada <matt@cicero.sh>
+// Licensed under either the Apache License, Version 2.0 OR the MIT License, at your option.
+// You may not use this file except in compliance with one of the Licenses.
+// Apache License text: https://www.apache.org/licenses/LICENSE-2.0
+// MIT License text: https://opensource.org/licenses/MIT
+
+use crate::error::CliError;
+use crate::help::CliHelpScreen;
+use crate::request::{CliFormat, CliRequest, CliValueSource};
+use crate::router::env_name;
+use std::env;
+#[cfg(feature = "config")]
+use std::path::PathBuf;
+
+/// Builds a populated command struct from a request.
+///
+/// Implemented by `#[derive(CliCommand)]` (requires the `derive` feature), which parses
+/// each field from the request's parameters and flags.
+pub trait CliFromRequest: Sized {
+    /// Creates the struct from the parameters and flags of a request.
+    fn from_request(req: &CliRequest) -> Result<Self, CliError>;
+}
+
+/// Executes a command whose fields have been populated from the request.
+///
+/// Implement this trait for structs using `#[derive(CliCommand)]` (requires the `derive`
+/// feature).  The generated `process()` method validates the request, populates a new
+/// instance of the struct from it, and calls `run()` on that instance.
+pub trait CliRun {
+    /// Executes the command.
+    ///
+    /// # Arguments
+    ///
+    /// * `req` - The CLI request the struct was populated from
+    fn run(&self, req: &CliRequest) -> anyhow::Result<()>;
+}
+
+/// Declarative specification of a command's positional parameters and flags.
+///
+/// Returned by `CliCommand::spec()`, the specification is declared once and used by the
+/// router to parse value flags, validate the request before `process()` is executed, and
+/// to populate the parameters and flags of the command's help screen.
+///
+/// # Example
+///
+/// ```
+/// use falcon_cli::*;
+///
+/// #[derive(Default)]
+/// struct CreateDomain;
+///
+/// impl CliCommand for CreateDomain {
+///     fn process(&self, req: &CliRequest) -> anyhow::Result<()> {
+///         // Parameters and flags have already been validated
+///         let domain = &req.args[0];
+///         let ip = req.get_flag("--ip-address").unwrap();
+///         Ok(())
+///     }
+///
+///     fn help(&self) -> CliHelpScreen {
+///         CliHelpScreen::new("Create Domain", "myapp domain create <DOMAIN> [TTL]", "Create a new domain")
+///     }
+///
+///     fn spec(&self) -> Option<CliCommandSpec> {
+///         let mut spec = CliCommandSpec::new();
+///         spec.add_param("DOMAIN", "Domain name to create", CliFormat::Any);
+///         spec.add_param("TTL", "Time to live in seconds", CliFormat::Integer).default("3600");
+///         spec.add_value_flag("-i", "--ip-address", "IP address of the domain", CliFormat::Any).required();
+///         spec.add_flag("-n", "--no-confirm", "Skip confirmation");
+///         Some(spec)
+///     }
+/// }
+///
+/// let mut router = CliRouter::new();
+/// router.add::<CreateDomain>("domain create", vec![], vec![]);
+///
+/// let (mut req, cmd) = router.lookup_from(["domain", "create", "example.com", "-i", "1.2.3.4", "-n"]).command().unwrap();
+/// cmd.spec().unwrap().apply(&mut req).unwrap();
+/// assert_eq!(req.args, vec!["example.com", "3600"]);
+/// assert_eq!(req.get_flag("--ip-address"), Some("1.2.3.4".to_string()));
+/// assert!(req.has_flag("--no-confirm"));
+/// ```
+#[derive(Clone, Default)]
+pub struct CliCommandSpec {
+    /// Positional parameters, in order.
+    pub params: Vec<CliParamSpec>,
+    /// Flags accepted by the command.
+    pub flags: Vec<CliFlagSpec>,
+}
+
+/// Specification of a single positional parameter.
+#[derive(Clone)]
+pub struct CliParamSpec {
+    /// Name of the parameter displayed in help screens (e.g., "DOMAIN").
+    pub name: String,
+    /// Description of the parameter.
+    pub description: String,
+    /// Format the parameter must conform to.
+    pub format: CliFormat,
+    /// Whether the parameter must be provided.
+    pub required: bool,
+    /// Value used when the parameter is not provided.
+    pub default: Option<String>,
+}
+
+/// Specification of a single flag.
+#[derive(Clone, Default)]
+pub struct CliFlagSpec {
+    /// Short form of the flag (e.g., "-o"), or empty.
+    pub short: String,
+    /// Long form of the flag (e.g., "--output"), or empty.
+    pub long: String,
+    /// Description of the flag.
+    pub description: String,
+    /// Whether the flag expects a value.
+    pub is_value: bool,
+    /// Format the flag's value must conform to.
+    pub format: CliFormat,
+    /// Whether the flag must be provided.
+    pub required: bool,
+    /// Whether the flag may be given multiple times.
+    pub repeatable: bool,
+    /// Value used when the flag is not provided.
+    pub default: Option<String>,
+    /// Environment variable used when the flag is not provided, before the default.
+    pub env: Option<String>,
+    /// Value loaded from a configuration file along with the file, used when the flag is not
+    /// provided or set via its environment variable.  Set by the router with the `config`
+    /// feature enabled.
+    #[cfg(feature = "config")]
+    pub config: Option<(String, PathBuf)>,
+}
+
+impl CliCommandSpec {
+    /// Creates a new, empty command specification.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a required positional parameter.
+    ///
+    /// Returns the parameter specification, which can be further modified via
+    /// `optional()` or `default()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the parameter displayed in help screens
+    /// * `description` - Description of the parameter
+    /// * `format` - Format the parameter must conform to
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliCommandSpec, CliFormat};
+    /// let mut spec = CliCommandSpec::new();
+    /// spec.add_param("FILE", "File to import", CliFormat::File);
+    /// spec.add_param("LIMIT", "Maximum rows to import", CliFormat::Integer).optional();
+    /// ```
+    pub fn add_param(
+        &mut self,
+        name: &str,
+        description: &str,
+        format: CliFormat,
+    ) -> &mut CliParamSpec {
+        self.params.push(CliParamSpec {
+            name: name.to_string(),
+            description: description.to_string(),
+            format,
+            required: true,
+            default: None,
+        });
+        self.params.last_mut().unwrap()
+    }
+
+    /// Adds a boolean flag.
+    ///
+    /// # Arguments
+    ///
+    /// * `short` - Short form of the flag (e.g., "-v"), or empty
+    /// * `long` - Long form of the flag (e.g., "--verbose"), or empty
+    /// * `description` - Description of the flag
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::CliCommandSpec;
+    /// let mut spec = CliCommandSpec::new();
+    /// spec.add_flag("-f", "--force", "Overwrite existing files");
+    /// ```
+    pub fn add_flag(&mut self, short: &str, long: &str, description: &str) -> &mut CliFlagSpec {
+        self.flags.push(CliFlagSpec {
+            short: short.to_string(),
+            long: long.to_string(),
+            description: description.to_string(),
+            ..Default::default()
+        });
+        self.flags.last_mut().unwrap()
+    }
+
+    /// Adds a flag that expects a value.
+    ///
+    /// Returns the flag specification, which can be further modified via `required()`,
+    /// `repeatable()`, `default()` or `env()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `short` - Short form of the flag (e.g., "-o"), or empty
+    /// * `long` - Long form of the flag (e.g., "--output"), or empty
+    /// * `description` - Description of the flag
+    /// * `format` - Format the flag's value must conform to
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliCommandSpec, CliFormat};
+    /// let mut spec = CliCommandSpec::new();
+    /// spec.add_value_flag("-p", "--port", "Port to listen on", CliFormat::IntegerRange(1..65536)).default("8080");
+    /// spec.add_value_flag("-t", "--tag", "Tag to apply", CliFormat::Any).repeatable();
+    /// ```
+    pub fn add_value_flag(
+        &mut self,
+        short: &str,
+        long: &str,
+        description: &str,
+        format: CliFormat,
+    ) -> &mut CliFlagSpec {
+        let flag = self.add_flag(short, long, description);
+        flag.is_value = true;
+        flag.format = format;
+        flag
+    }
+
+    /// Gets the specification of a flag by either its short or long form.
+    pub fn get_flag(&self, flag: &str) -> Option<&CliFlagSpec> {
+        self.flags.iter().find(|f| f.names().contains(&flag))
+    }
+
+    /// Applies the specification to a request.
+    ///
+    /// Copies flags given in one form to the other (eg. `-o` to `--output`) so they can be
+    /// retrieved by either name, fills in defaults for parameters and flags that were not
+    /// provided, and validates everything against the declared formats.  This is
+    /// automatically executed by `cli_run()` before the command's `process()` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `req` - The request to apply the specification to
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the request is valid, or a `CliError` describing the first issue found.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliCommandSpec, CliFormat, CliRequest};
+    /// let mut spec = CliCommandSpec::new();
+    /// spec.add_param("COUNT", "Number of items", CliFormat::Integer);
+    /// spec.add_value_flag("-o", "--output", "Output file", CliFormat::Any).required();
+    ///
+    /// let mut req = CliRequest { args: vec!["abc".to_string()], ..Default::default() };
+    /// let err = spec.apply(&mut req).unwrap_err();
+    /// assert_eq!(err.to_string(), "Invalid parameter at position 0: Expected integer, got 'abc'");
+    ///
+    /// let mut req = CliRequest { args: vec!["5".to_string()], ..Default::default() };
+    /// let err = spec.apply(&mut req).unwrap_err();
+    /// assert_eq!(err.to_string(), "Missing required flag, --output");
+    /// ```
+    pub fn apply(&self, req: &mut CliRequest) -> Result<(), CliError> {
+        // Parameters
+        for (pos, param) in self.params.iter().enumerate() {
+            if pos >= req.args.len() {
+                match &param.default {
+                    Some(default) => req.args.push(default.to_string()),
+                    None if param.required => return Err(CliError::MissingParams),
+                    None => break,
+                }
+            }
+            req.validate(pos, &req.args[pos], param.format.clone())?;
+        }
+
+        // Flags
+        for flag in self.flags.iter() {
+            flag.apply(req)?;
+        }
+
+        Ok(())
+    }
+
+    /// Generates a usage line from the declared parameters and flags.
+    ///
+    /// Used by the help screen when the command's help does not define a usage line.
+    ///
+    /// # Arguments
+    ///
+    /// * `cmd_alias` - The name of the command
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliCommandSpec, CliFormat};
+    /// let mut spec = CliCommandSpec::new();
+    /// spec.add_param("DOMAIN", "Domain name", CliFormat::Any);
+    /// spec.add_param("TTL", "Time to live", CliFormat::Integer).default("3600");
+    /// spec.add_flag("-n", "--no-confirm", "Skip confirmation");
+    /// assert_eq!(spec.usage("domain create"), "domain create <DOMAIN> [TTL] [FLAGS]");
+    /// ```
+    pub fn usage(&self, cmd_alias: &str) -> String {
+        let mut parts = vec![cmd_alias.to_string()];
+        for param in self.params.iter() {
+            if param.required {
+                parts.push(format!("<{}>", param.name));
+            } else {
+                parts.push(format!("[{}]", param.name));
+            }
+        }
+        if !self.flags.is_empty() {
+            parts.push("[FLAGS]".to_string());
+        }
+        parts.join(" ")
+    }
+
+    /// Adds the declared parameters and flags to a help screen.
+    ///
+    /// Entries already present within the help screen are left as is, other than noting the
+    /// environment variable of value flags as `[env: NAME]`.
+    pub fn extend_help(&self, help: &mut CliHelpScreen) {
+        for param in self.params.iter() {
+            if help.params.contains_key(&param.name) {
+                continue;
+            }
+            let mut desc = param.description.to_string();
+            if let Some(default) = &param.default {
+                desc = format!("{} (default: {})", desc, default);
+            } else if !param.required {
+                desc = format!("{} (optional)", desc);
+            }
+            help.add_param(&param.name, &desc);
+        }
+
+        for flag in self.flags.iter() {
+            if let Some(key) = flag.help_key(help) {
+                if let Some(env) = &flag.env
+                    && let Some(desc) = help.flags.get_mut(&key)
+                    && !desc.contains("[env: ")
+                {
+                    *desc = format!("{} [env: {}]", desc, env);
+                }
+                continue;
+            }
+            let key = flag.names().join("|");
+            let mut desc = flag.description.to_string();
+            if let Some(default) = &flag.default {
+                desc = format!("{} (default: {})", desc, default);
+            } else if flag.required {
+                desc = format!("{} (required)", desc);
+            }
+            if let Some(env) = &flag.env {
+                desc = format!("{} [env: {}]", desc, env);
+            }
+            help.add_flag(&key, &desc);
+        }
+    }
+
+    /// Sets the environment variable of each value flag without one, as derived from the
+    /// router's prefix set via `CliRouter::env_prefix()`.
+    pub(crate) fn derive_env(&mut self, prefix: &str) {
+        for flag in self.flags.iter_mut().filter(|f| f.is_value) {
+            flag.env = env_name(prefix, &flag.env, &flag.long);
+        }
+    }
+}
+
+impl CliParamSpec {
+    /// Marks the parameter as optional.
+    pub fn optional(&mut self) -> &mut Self {
+        self.required = false;
+        self
+    }
+
+    /// Sets the value used when the parameter is not provided, making it optional.
+    pub fn default(&mut self, value: &str) -> &mut Self {
+        self.required = false;
+        self.default = Some(value.to_string());
+        self
+    }
+}
+
+impl CliFlagSpec {
+    /// Marks the flag as required.
+    pub fn required(&mut self) -> &mut Self {
+        self.required = true;
+        self
+    }
+
+    /// Allows the flag to be given multiple times.
+    pub fn repeatable(&mut self) -> &mut Self {
+        self.repeatable = true;
+        self
+    }
+
+    /// Sets the value used when the flag is not provided.
+    pub fn default(&mut self, value: &str) -> &mut Self {
+        self.default = Some(value.to_string());
+        self
+    }
+
+    /// Sets the environment variable used when the value flag is not provided.
+    ///
+    /// The variable takes precedence over the default, and is ignored when empty.  It is
+    /// displayed within the help screen as `[env: NAME]`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use falcon_cli::{CliCommandSpec, CliFormat, CliRequest};
+    /// let mut spec = CliCommandSpec::new();
+    /// spec.add_value_flag("-r", "--region", "Region to deploy to", CliFormat::Any)
+    ///     .env("DOCTEST_SPEC_REGION")
+    ///     .default("us-east");
+    ///
+    /// // With DOCTEST_SPEC_REGION=eu-west set in the environment
+    /// # unsafe { std::env::set_var("DOCTEST_SPEC_REGION", "eu-west") };
+    /// let mut req = CliRequest::default();
+    /// spec.apply(&mut req).unwrap();
+    /// assert_eq!(req.get_flag("-r"), Some("eu-west".to_string()));
+    /// ```
+    pub fn env(&mut self, name: &str) -> &mut Self {
+        self.env = Some(name.to_string());
+        self
+    }
+
+    /// Gets the non-empty names of the flag, long form first.
+    pub fn names(&self) -> Vec<&str> {
+        [self.long.as_str(), self.short.as_str()].into_iter().filter(|n| !n.is_empty()).collect()
+    }
+
+    /// Gets the key of the help screen entry listing the flag under any of its names (eg.
+    /// `-o, --output`), if there is one.
+    pub(crate) fn help_key(&self, help: &CliHelpScreen) -> Option<String> {
+        let names = self.names();
+        help.flags
+            .keys()
+            .find(|key| {
+                key.split(|c: char| c == '|' || c == ',' || c.is_whitespace())
+                    .map(|f| f.split('=').next().unwrap_or(f))
+                    .any(|f| names.contains(&f))
+            })
+            .cloned()
+    }
+
+    /// Syncs the flag across both of its names, applies its default, and validates it.
+    fn apply(&self, req: &mut CliRequest) -> Result<(), CliError> {
+        let names = self.names();
+        let Some(name) = names.first().map(|n| n.to_string()) else {
+            return Ok(());
+        };
+
+        if !self.is_value {
+            let count: usize = names.iter().map(|n| req.get_flag_count(n)).sum();
+            req.flags.retain(|f| !names.contains(&f.as_str()));
+            for _ in 0..count {
+                req.flags.extend(names.iter().map(|n| n.to_string()));
+            }
+            if count == 0 && self.required {
+                return Err(CliError::MissingFlag(name));
+            }
+            return Ok(());
+        }
+
+        // Get value from the highest layer that has one
+        #[cfg(feature = "config")]
+        let config = self.config.clone().map(|(value, file)| (value, CliValueSource::Config(file)));
+        #[cfg(not(feature = "config"))]
+        let config = None;
+        let (value, source) = names
+            .iter()
+            .find_map(|n| req.get_flag(n))
+            .map(|v| (v, CliValueSource::CommandLine))
+            .or_else(|| {
+                let name = self.env.as_ref()?;
+                let value = env::var(name).ok().filter(|v| !v.is_empty())?;
+                Some((value, CliValueSource::Env(name.to_string())))
+            })
+            .or(config)
+            .or_else(|| self.default.clone().map(|v| (v, CliValueSource::Default)))
+            .unzip();
+
+        // Sync value and list across names
+        let values: Vec<String> =
+            names.iter().map(|n| req.get_flag_all(n)).max_by_key(|v| v.len()).unwrap_or_default();
+        for n in names.iter() {
+            if let Some(value) = &value {
+                req.flag_values.insert(n.to_string(), value.to_string());
+            }
+            if let Some(source) = &source {
+                req.flag_sources.insert(n.to_string(), source.clone());
+            }
+            if self.repeatable && !values.is_empty() {
+                req.flag_lists.insert(n.to_string(), values.clone());
+            }
+        }
+
+        if value.is_none() && self.required {
+            return Err(CliError::MissingFlag(name));
+        }
+
+        // Validate
+        let to_check = if values.is_empty() {
+            value.into_iter().collect()
+        } else {
+            values
+        };
+        for chk in to_check {
+            req.validate(0, &chk, self.format.clone()).map_err(|e| match e {
+                CliError::InvalidParam(_, msg) => CliError::InvalidFlag(name.to_string(), msg),
+                other => other,
+            })?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,329 @@
+// Copyright 2025 Aquila Labs of Alberta, Canada <matt@cicero.sh>
+// Licensed under either the Apache License, Version 2.0 OR the MIT License, at your option.
+// You may not use this file except in compliance with one of the Licenses.
+// Apache License text: https://www.apache.org/licenses/LICENSE-2.0
+// MIT License text: https://opensource.org/licenses/MIT
+
+use crate::error::CliError;
+use crate::output::{CliStream, is_terminal};
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+const RESET: &str = "\x1b[0m";
+
+static COLOR: AtomicU8 = AtomicU8::new(0);
+static THEME: RwLock<Option<CliTheme>> = RwLock::new(None);
+
+#[cfg(feature = "mock")]
+thread_local! {
+    static TEST_NO_COLORS: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+/// When colors and other styling are applied to output.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CliColor {
+    /// Only when writing to a terminal, and the `NO_COLOR` environment variable is not set.
+    #[default]
+    Auto,
+    /// Always, even when output is redirected.
+    Always,
+    /// Never.
+    Never,
+}
+
+impl FromStr for CliColor {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(CliColor::Auto),
+            "always" => Ok(CliColor::Always),
+            "never" => Ok(CliColor::Never),
+            _ => Err(CliError::InvalidFlag(
+                "--color".to_string(),
+                format!("Expected one of (auto / always / never), got '{}'", s),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for CliColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliColor::Auto => write!(f, "auto"),
+            CliColor::Always => write!(f, "always"),
+            CliColor::Never => write!(f, "never"),
+        }
+    }
+}
+
+/// The styles applied to each kind of output.
+///
+/// Each style is a space separated list of the style names accepted within markup, such as
+/// `"bold red"`, or an empty string for no styling.  The styles may also be used within
+/// markup by their field name, such as `[error]Failed[/]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliTheme {
+    /// Error messages, defaults to `red`.
+    pub error: String,
+    /// Warning messages, defaults to `yellow`.
+    pub warning: String,
+    /// Headers and section titles within help screens, defaults to `bold`.
+    pub header: String,
+    /// Parameters and flags within help screens, defaults to `cyan`.
+    pub flag: String,
+    /// Successful results, defaults to `green`.
+    pub success: String,
+}
+
+impl Default for CliTheme {
+    fn default() -> Self {
+        Self {
+            error: "red".to_string(),
+            warning: "yellow".to_string(),
+            header: "bold".to_string(),
+            flag: "cyan".to_string(),
+            success: "green".to_string(),
+        }
+    }
+}
+
+/// Sets when colors and other styling are applied to output.
+///
+/// Defaults to `CliColor::Auto`, which only applies styling when the output stream is a
+/// terminal that has not been redirected via `cli_set_output()`, and the `NO_COLOR`
+/// environment variable is not set.
+///
+/// # Arguments
+///
+/// * `color` - When to apply styling
+///
+/// # Example
+///
+/// ```
+/// use falcon_cli::{CliColor, cli_set_color};
+///
+/// cli_set_color(CliColor::Never);
+/// ```
+pub fn cli_set_color(color: CliColor) {
+    let value = match color {
+        CliColor::Auto => 0,
+        CliColor::Always => 1,
+        CliColor::Never => 2,
+    };
+    COLOR.store(value, Ordering::Relaxed);
+}
+
+/// Sets the theme used to style output.
+///
+/// # Arguments
+///
+/// * `theme` - The styles applied to each kind of output
+///
+/// # Example
+///
+/// ```
+/// use falcon_cli::{CliTheme, cli_set_theme};
+///
+/// cli_set_theme(CliTheme {
+///     error: "bold red".to_string(),
+///     flag: "green".to_string(),
+///     ..Default::default()
+/// });
+/// ```
+pub fn cli_set_theme(theme: CliTheme) {
+    *THEME.write().unwrap_or_else(|e| e.into_inner()) = Some(theme);
+}
+
+/// Converts inline markup within text into terminal styling.
+///
+/// Markup is a style name within square brackets, such as `[bold]`, `[red]` or `[bold red]`,
+/// which applies until `[/]`.  Available styles are bold, dim, italic and underline, the
+/// colors black, red, green, yellow, blue, magenta, cyan and white, and the theme styles
+/// error, warning, header, flag and success.  Anything else within square brackets is left
+/// as is, and `[[` displays a literal `[`.  When styling is disabled, the markup is removed
+/// instead.
+///
+/// Values interpolated into the output macros are converted along with the rest of the text,
+/// so untrusted values that may contain square brackets should be passed through
+/// `cli_escape()` first.
+///
+/// Text displayed via `cli_send!` and the other output macros is converted automatically,
+/// so this is only needed for text displayed by other means.
+///
+/// # Arguments
+///
+/// * `text` - The text containing markup
+///
+/// # Returns
+///
+/// Returns the text with markup converted for display on the `CliStream::Stdout` stream.
+///
+/// # Example
+///
+/// ```
+/// use falcon_cli::{CliColor, cli_set_color, cli_style};
+///
+/// cli_set_color(CliColor::Always);
+/// assert_eq!(cli_style("[bold red]Failed[/] [1/3]"), "\x1b[1;31mFailed\x1b[0m [1/3]");
+///
+/// cli_set_color(CliColor::Never);
+/// assert_eq!(cli_style("[bold red]Failed[/] [1/3]"), "Failed [1/3]");
+/// assert_eq!(cli_style("[[red] is literal"), "[red] is literal");
+/// ```
+pub fn cli_style(text: &str) -> String {
+    render_markup(text, CliStream::Stdout)
+}
+
+/// Escapes square brackets within text, so it is displayed as is rather than converted as
+/// markup.
+///
+/// # Arguments
+///
+/// * `text` - The text to escape, such as user provided data
+///
+/// # Returns
+///
+/// Returns the text with each `[` doubled.
+///
+/// # Example
+///
+/// ```
+/// use falcon_cli::{CliColor, cli_escape, cli_set_color, cli_style};
+///
+/// cli_set_color(CliColor::Never);
+/// let name = "[red]admin";
+/// assert_eq!(cli_escape(name), "[[red]admin");
+/// assert_eq!(cli_style(&format!("User: [bold]{}[/]", cli_escape(name))), "User: [red]admin");
+/// ```
+pub fn cli_escape(text: &str) -> String {
+    text.replace('[', "[[")
+}
+
+/// Checks whether styling is applied to output written to the given stream.
+pub(crate) fn colors_enabled(stream: CliStream) -> bool {
+    #[cfg(feature = "mock")]
+    if TEST_NO_COLORS.get() {
+        return false;
+    }
+
+    match COLOR.load(Ordering::Relaxed) {
+        1 => true,
+        2 => false,
+        _ => env::var_os("NO_COLOR").is_none_or(|val| val.is_empty()) && is_terminal(stream),
+    }
+}
+
+/// Disables styling on the current thread, regardless of `cli_set_color()`, returning the
+/// previous setting.
+#[cfg(feature = "mock")]
+pub(crate) fn set_test_no_colors(disabled: bool) -> bool {
+    TEST_NO_COLORS.replace(disabled)
+}
+
+/// Converts markup within the text for display on the given stream.
+pub(crate) fn render_markup(text: &str, stream: CliStream) -> String {
+    convert_markup(text, colors_enabled(stream))
+}
+
+/// Removes all markup from the text.
+#[cfg(feature = "log")]
+pub(crate) fn strip_markup(text: &str) -> String {
+    convert_markup(text, false)
+}
+
+fn convert_markup(text: &str, enabled: bool) -> String {
+    let theme = theme();
+
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('[') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if rest.starts_with("[[") {
+            output.push('[');
+            rest = &rest[2..];
+            continue;
+        }
+
+        let Some(end) = rest.find(']') else {
+            break;
+        };
+        match tag_codes(&rest[1..end], &theme) {
+            Some(codes) => {
+                if enabled {
+                    output += &codes;
+                }
+                rest = &rest[end + 1..];
+            }
+            None => {
+                output.push('[');
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+
+    output
+}
+
+/// Applies one of the theme's styles to the text, if styling is enabled for the stream.
+pub(crate) fn paint(text: &str, style: &str, stream: CliStream) -> String {
+    if !colors_enabled(stream) {
+        return text.to_string();
+    }
+
+    match tag_codes(style, &theme()) {
+        Some(codes) if !codes.is_empty() => format!("{}{}{}", codes, text, RESET),
+        _ => text.to_string(),
+    }
+}
+
+fn theme() -> CliTheme {
+    THEME.read().unwrap_or_else(|e| e.into_inner()).clone().unwrap_or_default()
+}
+
+/// Gets the escape sequence for the contents of a markup tag, or `None` if it is not markup.
+fn tag_codes(tag: &str, theme: &CliTheme) -> Option<String> {
+    let style = match tag {
+        "/" => return Some(RESET.to_string()),
+        "error" => &theme.error,
+        "warning" => &theme.warning,
+        "header" => &theme.header,
+        "flag" => &theme.flag,
+        "success" => &theme.success,
+        _ => return style_codes(tag),
+    };
+    Some(style_codes(style).unwrap_or_default())
+}
+
+/// Gets the escape sequence for a space separated list of style names.
+fn style_codes(style: &str) -> Option<String> {
+    let codes = style
+        .split_whitespace()
+        .map(|name| match name {
+            "bold" => Some("1"),
+            "dim" => Some("2"),
+            "italic" => Some("3"),
+            "underline" => Some("4"),
+            "black" => Some("30"),
+            "red" => Some("31"),
+            "green" => Some("32"),
+            "yellow" => Some("33"),
+            "blue" => Some("34"),
+            "magenta" => Some("35"),
+            "cyan" => Some("36"),
+            "white" => Some("37"),
+            _ => None,
+        })
+        .collect::<Option<Vec<&str>>>()?;
+
+    if codes.is_empty() {
+        return None;
+    }
+    Some(format!("\x1b[{}m", codes.join(";")))
+}
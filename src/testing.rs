@@ -0,0 +1,458 @@
+// Copyright 2025 Aquila Labs of Alberta, Canada <matt@cicero.sh>
+// Licensed under either the Apache License, Version 2.0 OR the MIT License, at your option.
+// You may not use this file except in compliance with one of the Licenses.
+// Apache License text: https://www.apache.org/licenses/LICENSE-2.0
+// MIT License text: https://opensource.org/licenses/MIT
+
+use crate::help::CliHelpScreen;
+use crate::output::{CliBuffer, set_capture, set_test_width};
+use crate::router::CliRouter;
+use crate::style::set_test_no_colors;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::ffi::OsString;
+use std::path::Path;
+use std::{env, fs};
+
+thread_local! {
+    static ANSWERS: RefCell<Option<VecDeque<String>>> = const { RefCell::new(None) };
+}
+
+/// The result of running a command via `run()`.
+#[derive(Debug, Clone)]
+pub struct CliTestResult {
+    /// Everything written to the `CliStream::Stdout` stream.
+    pub stdout: String,
+    /// Everything written to the `CliStream::Stderr` stream.
+    pub stderr: String,
+    /// The exit code `cli_run()` would have returned.
+    pub exit_code: u8,
+}
+
+impl CliTestResult {
+    /// Whether the command completed successfully, with an exit code of 0.
+    pub fn success(&self) -> bool {
+        self.exit_code == 0
+    }
+}
+
+/// Queues scripted answers for the input functions on the current thread.
+///
+/// Each call to `cli_get_input()`, `cli_confirm()`, `cli_get_option()`, `cli_get_password()`
+/// and `cli_get_new_password()` takes the next answer, as does each line read by
+/// `cli_get_multiline_input()` (end it with an empty answer) and each call to
+/// `cli_text_editor()`, which returns the answer as the edited contents.
+///
+/// Once answers have been queued, prompting after all have been used panics instead of
+/// reading STDIN, until `clear_answers()` is called.
+///
+/// # Arguments
+///
+/// * `answers` - The answers to queue, in the order they will be given
+///
+/// # Example
+///
+/// ```
+/// use falcon_cli::testing::{clear_answers, queue_answers};
+/// use falcon_cli::{cli_confirm, cli_get_input};
+///
+/// queue_answers(["", "y"]);
+/// assert_eq!(cli_get_input("Name: ", "Anonymous"), "Anonymous");
+/// assert!(cli_confirm("Continue?"));
+/// clear_answers();
+/// ```
+pub fn queue_answers<I, S>(answers: I)
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    ANSWERS.with_borrow_mut(|queue| {
+        queue.get_or_insert_with(VecDeque::new).extend(answers.into_iter().map(Into::into))
+    });
+}
+
+/// Clears all queued answers, so input is read from STDIN again.
+pub fn clear_answers() {
+    ANSWERS.set(None);
+}
+
+/// Gets the next scripted answer, or `None` if answers are not being scripted.
+pub(crate) fn next_answer() -> Option<String> {
+    ANSWERS.with_borrow_mut(|queue| {
+        let queue = queue.as_mut()?;
+        match queue.pop_front() {
+            Some(answer) => Some(answer),
+            None => panic!("No scripted answers left, queue more with testing::queue_answers()"),
+        }
+    })
+}
+
+/// Checks whether any scripted answers are queued.
+pub(crate) fn has_answers() -> bool {
+    ANSWERS.with_borrow(|queue| queue.as_ref().is_some_and(|queue| !queue.is_empty()))
+}
+
+/// Runs the arguments through the router as `cli_run()` would, capturing all output.
+///
+/// Output is only captured from the current thread, so tests may run in parallel.
+///
+/// # Arguments
+///
+/// * `router` - The CLI router containing all registered commands
+/// * `args` - The arguments to run, excluding the program name
+///
+/// # Returns
+///
+/// Returns the captured output along with the exit code.
+///
+/// # Example
+///
+/// ```
+/// use falcon_cli::testing::{queue_answers, run};
+/// use falcon_cli::{CliCommand, CliHelpScreen, CliRequest, CliRouter, cli_confirm, cli_sendln};
+///
+/// #[derive(Default)]
+/// struct DeleteUser;
+/// impl CliCommand for DeleteUser {
+///     fn process(&self, req: &CliRequest) -> anyhow::Result<()> {
+///         req.require_params(1)?;
+///         if cli_confirm(&format!("Delete {}?", req.args[0])) {
+///             cli_sendln!("Deleted {}", req.args[0]);
+///         }
+///         Ok(())
+///     }
+///     fn help(&self) -> CliHelpScreen { CliHelpScreen::new("Delete User", "user delete <NAME>", "") }
+/// }
+///
+/// let mut router = CliRouter::new();
+/// router.add::<DeleteUser>("user delete", vec![], vec![]);
+///
+/// queue_answers(["y"]);
+/// let result = run(&mut router, ["user", "delete", "alice"]);
+/// assert!(result.success());
+/// assert!(result.stdout.ends_with("Deleted alice\n"));
+///
+/// let result = run(&mut router, ["user", "delete"]);
+/// assert_eq!(result.exit_code, 64);
+/// assert!(result.stderr.starts_with("ERROR: "));
+/// ```
+pub fn run<I>(router: &mut CliRouter, args: I) -> CliTestResult
+where
+    I: IntoIterator,
+    I::Item: Into<OsString>,
+{
+    let (exit_code, stdout, stderr) = capture(|| crate::run_args(router, args));
+    CliTestResult {
+        stdout,
+        stderr,
+        exit_code,
+    }
+}
+
+/// Executes a closure while capturing all output written from the current thread.
+///
+/// Unless a width was set via `cli_set_width()`, output is rendered at a fixed width of 80
+/// columns and without styling, so it does not depend on the terminal the tests are run
+/// from.
+///
+/// # Arguments
+///
+/// * `func` - The closure to execute
+///
+/// # Returns
+///
+/// Returns the closure's result, along with the captured STDOUT and STDERR output.
+///
+/// # Example
+///
+/// ```
+/// use falcon_cli::cli_header;
+/// use falcon_cli::testing::capture;
+///
+/// let (_, stdout, _) = capture(|| cli_header("Report"));
+/// assert!(stdout.contains("-- Report\n"));
+/// ```
+pub fn capture<F, T>(func: F) -> (T, String, String)
+where
+    F: FnOnce() -> T,
+{
+    /// Restores the previous capture, even if the closure panics.
+    struct Restore(Option<Option<(CliBuffer, CliBuffer)>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            if let Some(previous) = self.0.take() {
+                set_capture(previous);
+            }
+        }
+    }
+
+    let (stdout, stderr) = (CliBuffer::new(), CliBuffer::new());
+    let restore = Restore(Some(set_capture(Some((stdout.clone(), stderr.clone())))));
+    let result = with_fixed_width(func);
+    drop(restore);
+
+    (result, stdout.contents(), stderr.contents())
+}
+
+/// Renders every help page of the router, keyed by snapshot name.
+///
+/// Includes the help index as `index`, each category as `category-<alias>`, and each
+/// command as `command-<alias>`, with spaces within aliases replaced by dashes.  Pages are
+/// rendered without styling and, unless a width was set via `cli_set_width()`, at a fixed
+/// width of 80 columns.
+///
+/// # Arguments
+///
+/// * `router` - The CLI router containing all registered commands and categories
+///
+/// # Returns
+///
+/// Returns the rendered pages, sorted by name.
+///
+/// # Example
+///
+/// ```
+/// # use falcon_cli::{CliRouter, CliCommand, CliRequest, CliHelpScreen};
+/// # #[derive(Default)]
+/// # struct CreateDomain;
+/// # impl CliCommand for CreateDomain {
+/// #   fn process(&self, req: &CliRequest) -> anyhow::Result<()> { Ok(()) }
+/// #   fn help(&self) -> CliHelpScreen { CliHelpScreen::new("Create Domain", "domain create", "") }
+/// # }
+/// use falcon_cli::testing::help_pages;
+///
+/// let mut router = CliRouter::new();
+/// router.add_category("domain", "Domains", "Manage domains");
+/// router.add::<CreateDomain>("domain create", vec![], vec![]);
+///
+/// let pages = help_pages(&router);
+/// assert_eq!(pages.keys().collect::<Vec<_>>(), vec!["category-domain", "command-domain-create", "index"]);
+/// ```
+pub fn help_pages(router: &CliRouter) -> BTreeMap<String, String> {
+    with_fixed_width(|| format_help_pages(router))
+}
+
+fn format_help_pages(router: &CliRouter) -> BTreeMap<String, String> {
+    let mut pages = BTreeMap::new();
+    pages.insert("index".to_string(), CliHelpScreen::format_index(router));
+
+    for cat_alias in router.categories.keys() {
+        pages.insert(
+            format!("category-{}", cat_alias.replace(' ', "-")),
+            CliHelpScreen::format_category(router, cat_alias),
+        );
+    }
+
+    for alias in router.commands.keys() {
+        pages.insert(
+            format!("command-{}", alias.replace(' ', "-")),
+            CliHelpScreen::format(router, alias),
+        );
+    }
+
+    pages
+}
+
+/// Compares every help page of the router against the snapshot files within a directory.
+///
+/// Each page from `help_pages()` is compared against `<dir>/<name>.txt`, and snapshots
+/// without a matching page are reported as stale.  When the `FALCON_CLI_BLESS` environment
+/// variable is set, the snapshots are written instead, and stale ones removed.
+///
+/// # Arguments
+///
+/// * `router` - The CLI router containing all registered commands and categories
+/// * `dir` - Directory containing the snapshot files
+///
+/// # Panics
+///
+/// Panics listing every page that is missing, stale or differs from its snapshot.
+///
+/// # Example
+///
+/// ```no_run
+/// # use falcon_cli::CliRouter;
+/// # fn build_router() -> CliRouter { CliRouter::new() }
+/// use falcon_cli::testing::assert_help_snapshots;
+///
+/// let router = build_router();
+/// assert_help_snapshots(&router, "tests/snapshots/help");
+/// ```
+pub fn assert_help_snapshots<P: AsRef<Path>>(router: &CliRouter, dir: P) {
+    let dir = dir.as_ref();
+    let pages = help_pages(router);
+
+    // Get existing snapshots
+    let existing: Vec<String> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    entry.file_name().to_string_lossy().strip_suffix(".txt").map(|n| n.to_string())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let stale: Vec<&String> = existing.iter().filter(|name| !pages.contains_key(*name)).collect();
+
+    // Bless
+    if is_bless() {
+        for name in stale {
+            let _ = fs::remove_file(dir.join(format!("{}.txt", name)));
+        }
+        for (name, contents) in pages.iter() {
+            write_snapshot(&dir.join(format!("{}.txt", name)), contents);
+        }
+        return;
+    }
+
+    let mut failures: Vec<String> = stale
+        .iter()
+        .map(|name| format!("{}: stale snapshot, page no longer exists", name))
+        .collect();
+    for (name, contents) in pages.iter() {
+        if let Err(e) = compare_snapshot(&dir.join(format!("{}.txt", name)), contents) {
+            failures.push(format!("{}: {}", name, e));
+        }
+    }
+
+    if !failures.is_empty() {
+        panic!(
+            "Help snapshots do not match, re-run with FALCON_CLI_BLESS=1 to update them\n\n{}",
+            failures.join("\n")
+        );
+    }
+}
+
+/// Compares output, such as a captured table, against a snapshot file.
+///
+/// When the `FALCON_CLI_BLESS` environment variable is set, the snapshot is written
+/// instead.
+///
+/// # Arguments
+///
+/// * `path` - Location of the snapshot file
+/// * `actual` - The output to compare
+///
+/// # Panics
+///
+/// Panics if the snapshot is missing or differs from the output.
+///
+/// # Example
+///
+/// ```no_run
+/// use falcon_cli::cli_display_table;
+/// use falcon_cli::testing::{assert_snapshot, capture};
+///
+/// let (_, stdout, _) = capture(|| cli_display_table(&["Name", "Age"], &[vec!["Alice", "30"]]));
+/// assert_snapshot("tests/snapshots/users_table.txt", &stdout);
+/// ```
+pub fn assert_snapshot<P: AsRef<Path>>(path: P, actual: &str) {
+    let path = path.as_ref();
+    if is_bless() {
+        write_snapshot(path, actual);
+    } else if let Err(e) = compare_snapshot(path, actual) {
+        panic!(
+            "Snapshot {} {}, re-run with FALCON_CLI_BLESS=1 to update it",
+            path.display(),
+            e
+        );
+    }
+}
+
+/// Whether snapshots should be written instead of compared.
+fn is_bless() -> bool {
+    env::var("FALCON_CLI_BLESS").is_ok_and(|v| !v.is_empty() && v != "0")
+}
+
+fn write_snapshot(path: &Path, contents: &str) {
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Err(e) = fs::write(path, contents) {
+        panic!("Unable to write snapshot {}: {}", path.display(), e);
+    }
+}
+
+/// Compares the contents against a snapshot, describing the first difference.
+fn compare_snapshot(path: &Path, actual: &str) -> Result<(), String> {
+    let Ok(expected) = fs::read_to_string(path) else {
+        return Err("is missing".to_string());
+    };
+    if expected == actual {
+        return Ok(());
+    } else if expected.trim_end_matches(['\r', '\n']) == actual.trim_end_matches(['\r', '\n']) {
+        return Err(format!(
+            "differs only in trailing newline\n    expected: {} trailing newline(s)\n    \
+             actual:   {} trailing newline(s)",
+            trailing_newlines(&expected),
+            trailing_newlines(actual)
+        ));
+    }
+
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut line = 1;
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(e), Some(a)) if e == a => line += 1,
+            (None, None) => return Err("differs only in line endings".to_string()),
+            (e, a) => {
+                return Err(format!(
+                    "differs at line {}\n    expected: {:?}\n    actual:   {:?}",
+                    line,
+                    e.unwrap_or("<end of file>"),
+                    a.unwrap_or("<end of file>")
+                ));
+            }
+        }
+    }
+}
+
+/// Counts the newlines at the end of the text.
+fn trailing_newlines(text: &str) -> usize {
+    let content = text.trim_end_matches(['\r', '\n']);
+    text[content.len()..].matches('\n').count()
+}
+
+/// Executes a closure with output rendered at a fixed width of 80 columns, and without
+/// styling.
+fn with_fixed_width<F, T>(func: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    /// Restores the previous width and styling, even if the closure panics.
+    struct Restore(Option<usize>, bool);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            set_test_width(self.0);
+            set_test_no_colors(self.1);
+        }
+    }
+
+    let _restore = Restore(set_test_width(Some(80)), set_test_no_colors(true));
+    func()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_trailing_newline() {
+        let path = env::temp_dir().join("falcon-testing-snapshot.txt");
+        fs::write(&path, "a\nb\n").unwrap();
+
+        assert!(compare_snapshot(&path, "a\nb\n").is_ok());
+        let err = compare_snapshot(&path, "a\nb").unwrap_err();
+        assert!(err.starts_with("differs only in trailing newline"));
+        assert!(err.contains("expected: 1 trailing newline(s)"));
+        assert!(err.contains("actual:   0 trailing newline(s)"));
+        assert_eq!(
+            compare_snapshot(&path, "a\r\nb\n").unwrap_err(),
+            "differs only in line endings"
+        );
+        assert!(compare_snapshot(&path, "a\nc\n").unwrap_err().starts_with("differs at line 2"));
+        fs::remove_file(&path).unwrap();
+    }
+}